@@ -0,0 +1,43 @@
+// times the circuit simulation on its own, without starting the game. run it with cargo bench.
+// it's a plain main instead of a test harness, since benchmark tests need a nightly compiler
+
+use std::{collections::HashMap, time::Instant};
+
+use bevy_game_4::{run_logic_tick, BlockGrid, BlockType, UpdateQueue};
+
+// how many inverter clocks are run side by side, and how many ticks they're run for
+const CLOCKS: i32 = 50;
+const TICKS: usize = 1000;
+// how long the wire each clock drives is
+const WIRE_LENGTH: i32 = 20;
+
+fn main() {
+    let mut grid: HashMap<(i32, i32), BlockType> = HashMap::new();
+    let mut updates = UpdateQueue::default();
+    // every clock is an inverter wired back into itself, driving a long wire to the right
+    for clock in 0..CLOCKS {
+        let y = clock * 3;
+        grid.set_block((0, y), BlockType::Inverter(1, 0, 1));
+        for pos in [(1, y), (1, y + 1), (0, y + 1)] {
+            grid.set_block(pos, BlockType::Wire(0));
+        }
+        for x in 2..WIRE_LENGTH {
+            grid.set_block((x, y), BlockType::Wire(0));
+        }
+        updates.create_block_update((0, y));
+    }
+
+    let start = Instant::now();
+    for _ in 0..TICKS {
+        run_logic_tick(&mut grid, &mut updates);
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{} clocks driving {} wires each: {:?} for {} ticks, {:?} a tick",
+        CLOCKS,
+        WIRE_LENGTH,
+        elapsed,
+        TICKS,
+        elapsed / TICKS as u32
+    );
+}
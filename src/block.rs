@@ -1,23 +1,38 @@
 use bevy::prelude::*;
+use std::f32::consts::PI;
 
-use crate::{create_block_update, BlockUpdateQueue, Map};
-
-#[derive(PartialEq, Hash, Eq, Clone, Copy, Debug)]
-pub enum BlockType {
-    Wood,
-    Stone(i32),
-    Wire(i32),
-    Repeater(i32, i32),
-    Inverter(i32, i32),
-}
+use crate::{
+    create_block_update, get_gate_output, parse_word_or, BlockType, BlockUpdateQueue,
+    ChestInventory, GateType, Map, ParseErrorReason, COMPARATOR_COMPARE_MODE,
+    COMPARATOR_SUBTRACT_MODE, DEFAULT_PROCESSOR_DELAY, MAX_PROCESSOR_DELAY, RAM_SIZE,
+};
 
 #[derive(PartialEq, Hash, Eq, Clone, Copy, Debug)]
 pub enum ItemType {
     Wood,
     Stone(i32),
     Wire(i32),
+    DirectionalWire(i32, i32),
     Repeater(i32, i32),
     Inverter(i32, i32),
+    Gate(GateType),
+    Lever,
+    Button,
+    PressurePlate,
+    Lamp,
+    SevenSegmentDisplay,
+    Comparator,
+    SrLatch,
+    DFlipFlop,
+    Ram,
+    ChunkLoader,
+    Chest,
+    Conveyor,
+    Inserter,
+    // ores can't be placed, they're only used to make other things
+    Copper,
+    Iron,
+    RedstoneDust,
 }
 
 #[derive(Component)]
@@ -31,19 +46,37 @@ pub fn get_block_breaking_speed_multiplier(block_type: BlockType) -> f32 {
         BlockType::Wood => 10.,
         BlockType::Stone(_) => 1.,
         BlockType::Wire(_) => 100.,
-        BlockType::Repeater(_, _) => 100.,
-        BlockType::Inverter(_, _) => 100.,
+        BlockType::DirectionalWire(_, _) => 100.,
+        BlockType::Repeater(..) => 100.,
+        BlockType::Inverter(..) => 100.,
+        BlockType::Gate(..) => 100.,
+        BlockType::Lever(_) => 100.,
+        BlockType::Button(_) => 100.,
+        BlockType::PressurePlate(_) => 100.,
+        BlockType::Lamp(_) => 100.,
+        BlockType::SevenSegmentDisplay(_) => 100.,
+        BlockType::Comparator(..) => 100.,
+        BlockType::SrLatch(..) => 100.,
+        BlockType::DFlipFlop(..) => 100.,
+        BlockType::Ram(..) => 100.,
+        BlockType::ChunkLoader => 10.,
+        BlockType::Chest => 2.,
+        BlockType::Conveyor(..) => 100.,
+        BlockType::Inserter(..) => 100.,
     }
 }
 
 pub fn despawn_block(
-    mut commands: Commands,
-    mut block_map: ResMut<Map>,
+    commands: &mut Commands,
+    block_map: &mut ResMut<Map>,
     pos: (i32, i32),
 ) -> Option<BlockType> {
     if let Some((entity, block_type)) = block_map.blocks.remove(&pos) {
+        block_map.pressure_plates.remove(&pos);
+        block_map.chunk_loaders.remove(&pos);
+        block_map.inserters.remove(&pos);
         commands.entity(entity).despawn();
-        return Some(block_type.clone());
+        return Some(block_type);
     }
     None
 }
@@ -53,65 +86,222 @@ pub fn get_block_color(block_type: BlockType) -> Color {
         BlockType::Wood => Color::rgb(0.4, 0.2, 0.),
         BlockType::Stone(_) => Color::rgb(0.3, 0.3, 0.3),
         BlockType::Wire(power) => Color::rgb(0.4 + 0.004 * power as f32, 0., 0.),
-        BlockType::Repeater(power, _) => default(),
-        BlockType::Inverter(power, _) => default(),
+        BlockType::DirectionalWire(power, _) => Color::rgb(0.4 + 0.004 * power as f32, 0., 0.),
+        BlockType::Repeater(_, _, delay) => get_processor_delay_color(delay),
+        BlockType::Inverter(_, _, delay) => get_processor_delay_color(delay),
+        BlockType::Gate(_, _, _, delay) => get_processor_delay_color(delay),
+        BlockType::Lever(_) => default(),
+        BlockType::Button(_) => default(),
+        BlockType::PressurePlate(_) => default(),
+        // colours above 1 are picked up by the bloom on the camera, which makes them glow
+        BlockType::Lamp(power) => {
+            if power == 0 {
+                Color::rgb(0.35, 0.3, 0.2)
+            } else {
+                Color::rgb(4., 3.4, 1.6)
+            }
+        }
+        BlockType::SevenSegmentDisplay(_) => Color::rgb(2., 2., 2.),
+        BlockType::Comparator(..) => default(),
+        BlockType::SrLatch(..) => default(),
+        BlockType::DFlipFlop(..) => default(),
+        BlockType::Ram(..) => default(),
+        BlockType::ChunkLoader => default(),
+        BlockType::Chest => Color::rgb(0.55, 0.35, 0.12),
+        BlockType::Conveyor(..) => default(),
+        BlockType::Inserter(..) => default(),
     }
 }
 
+// repeaters, inverters and gates get darker the longer their delay is
+fn get_processor_delay_color(delay: i32) -> Color {
+    let brightness = 1. - 0.15 * (delay - 1) as f32;
+    Color::rgb(brightness, brightness, brightness)
+}
+
 pub fn get_item_color(item_type: ItemType) -> Color {
     match item_type {
         ItemType::Wood => Color::rgb(0.4, 0.2, 0.),
         ItemType::Stone(_) => Color::rgb(0.3, 0.3, 0.3),
         ItemType::Wire(power) => Color::rgb(0.4 + 0.004 * power as f32, 0., 0.),
-        ItemType::Repeater(power, _) => default(),
-        ItemType::Inverter(power, _) => default(),
+        ItemType::DirectionalWire(power, _) => Color::rgb(0.4 + 0.004 * power as f32, 0., 0.),
+        ItemType::Repeater(_, _) => default(),
+        ItemType::Inverter(_, _) => default(),
+        ItemType::Gate(_) => default(),
+        ItemType::Lever => default(),
+        ItemType::Button => default(),
+        ItemType::PressurePlate => default(),
+        ItemType::Lamp => Color::rgb(0.35, 0.3, 0.2),
+        ItemType::SevenSegmentDisplay => default(),
+        ItemType::Comparator => default(),
+        ItemType::SrLatch => default(),
+        ItemType::DFlipFlop => default(),
+        ItemType::Ram => default(),
+        ItemType::ChunkLoader => default(),
+        ItemType::Chest => Color::rgb(0.55, 0.35, 0.12),
+        ItemType::Conveyor => default(),
+        ItemType::Inserter => default(),
+        ItemType::Copper => Color::rgb(0.75, 0.45, 0.2),
+        ItemType::Iron => Color::rgb(0.75, 0.72, 0.7),
+        ItemType::RedstoneDust => Color::rgb(0.8, 0.05, 0.05),
     }
 }
 
 pub fn get_block_dir(block_type: BlockType) -> i32 {
     match block_type {
-        BlockType::Repeater(_, dir) => dir,
-        BlockType::Inverter(_, dir) => dir,
+        BlockType::DirectionalWire(_, dir) => dir,
+        BlockType::Repeater(_, dir, _) => dir,
+        BlockType::Inverter(_, dir, _) => dir,
+        BlockType::Gate(_, _, dir, _) => dir,
+        BlockType::Comparator(_, dir, _) => dir,
+        BlockType::SrLatch(_, dir, _) => dir,
+        BlockType::DFlipFlop(_, dir, _, _) => dir,
+        BlockType::Ram(_, dir, _) => dir,
+        BlockType::Conveyor(_, dir) => dir,
+        BlockType::Inserter(_, dir) => dir,
         _ => 0,
     }
 }
 
+// directional wires are drawn thinner, so that the way they're facing can be seen
+pub fn get_block_size(block_type: BlockType) -> Vec2 {
+    match block_type {
+        BlockType::DirectionalWire(_, _) => Vec2::new(0.4, 1.),
+        _ => Vec2::new(1., 1.),
+    }
+}
+
 pub fn get_block_texture(block_type: BlockType, asset_server: &Res<AssetServer>) -> Handle<Image> {
     match block_type {
-        BlockType::Inverter(power, dir) => {
+        BlockType::Inverter(power, _, _) => {
             if power == 0 {
                 asset_server.load("inverter_powered.png")
             } else {
                 asset_server.load("inverter_unpowered.png")
             }
         }
-        BlockType::Repeater(power, dir) => {
+        BlockType::Repeater(power, _, _) => {
             if power == 0 {
                 asset_server.load("repeater_unpowered.png")
             } else {
                 asset_server.load("repeater_powered.png")
             }
         }
+        BlockType::Gate(gate_type, power, _, _) => {
+            let gate_name = get_string_rep_from_gate_type(gate_type);
+            if power == 0 {
+                asset_server.load(format!("{}_unpowered.png", gate_name))
+            } else {
+                asset_server.load(format!("{}_powered.png", gate_name))
+            }
+        }
+        BlockType::Lever(power) => {
+            if power == 0 {
+                asset_server.load("lever_off.png")
+            } else {
+                asset_server.load("lever_on.png")
+            }
+        }
+        BlockType::Button(power) => {
+            if power == 0 {
+                asset_server.load("button_off.png")
+            } else {
+                asset_server.load("button_on.png")
+            }
+        }
+        BlockType::PressurePlate(power) => {
+            if power == 0 {
+                asset_server.load("pressure_plate_off.png")
+            } else {
+                asset_server.load("pressure_plate_on.png")
+            }
+        }
+        BlockType::SevenSegmentDisplay(value) => {
+            asset_server.load(format!("seven_segment_{:x}.png", value))
+        }
+        BlockType::Comparator(power, _, mode) => {
+            let mode_name = if mode == COMPARATOR_SUBTRACT_MODE {
+                "subtract"
+            } else {
+                "compare"
+            };
+            if power == 0 {
+                asset_server.load(format!("comparator_{}_unpowered.png", mode_name))
+            } else {
+                asset_server.load(format!("comparator_{}_powered.png", mode_name))
+            }
+        }
+        BlockType::SrLatch(power, ..) => {
+            if power == 0 {
+                asset_server.load("sr_latch_unpowered.png")
+            } else {
+                asset_server.load("sr_latch_powered.png")
+            }
+        }
+        BlockType::DFlipFlop(power, ..) => {
+            if power == 0 {
+                asset_server.load("d_flip_flop_unpowered.png")
+            } else {
+                asset_server.load("d_flip_flop_powered.png")
+            }
+        }
+        BlockType::Ram(power, ..) => {
+            if power == 0 {
+                asset_server.load("ram_unpowered.png")
+            } else {
+                asset_server.load("ram_powered.png")
+            }
+        }
+        BlockType::ChunkLoader => asset_server.load("chunk_loader.png"),
+        BlockType::Conveyor(power, _) => {
+            if power == 0 {
+                asset_server.load("conveyor_unpowered.png")
+            } else {
+                asset_server.load("conveyor_powered.png")
+            }
+        }
+        BlockType::Inserter(power, _) => {
+            if power == 0 {
+                asset_server.load("inserter_unpowered.png")
+            } else {
+                asset_server.load("inserter_powered.png")
+            }
+        }
         _ => default(),
     }
 }
 
 pub fn get_item_texture(item_type: ItemType, asset_server: &Res<AssetServer>) -> Handle<Image> {
     match item_type {
-        ItemType::Inverter(power, dir) => {
+        ItemType::Inverter(power, _) => {
             if power == 0 {
                 asset_server.load("inverter_powered.png")
             } else {
                 asset_server.load("inverter_unpowered.png")
             }
         }
-        ItemType::Repeater(power, dir) => {
+        ItemType::Repeater(power, _) => {
             if power == 0 {
                 asset_server.load("repeater_unpowered.png")
             } else {
                 asset_server.load("repeater_powered.png")
             }
         }
+        ItemType::Gate(gate_type) => asset_server.load(format!(
+            "{}_unpowered.png",
+            get_string_rep_from_gate_type(gate_type)
+        )),
+        ItemType::Lever => asset_server.load("lever_off.png"),
+        ItemType::Button => asset_server.load("button_off.png"),
+        ItemType::PressurePlate => asset_server.load("pressure_plate_off.png"),
+        ItemType::SevenSegmentDisplay => asset_server.load("seven_segment_8.png"),
+        ItemType::Comparator => asset_server.load("comparator_compare_unpowered.png"),
+        ItemType::SrLatch => asset_server.load("sr_latch_unpowered.png"),
+        ItemType::DFlipFlop => asset_server.load("d_flip_flop_unpowered.png"),
+        ItemType::Ram => asset_server.load("ram_unpowered.png"),
+        ItemType::ChunkLoader => asset_server.load("chunk_loader.png"),
+        ItemType::Conveyor => asset_server.load("conveyor_unpowered.png"),
+        ItemType::Inserter => asset_server.load("inserter_unpowered.png"),
         _ => default(),
     }
 }
@@ -129,7 +319,7 @@ pub fn spawn_block(
             SpriteBundle {
                 sprite: Sprite {
                     color: { get_block_color(block_type) },
-                    custom_size: Some(Vec2::new(1., 1.)),
+                    custom_size: Some(get_block_size(block_type)),
                     ..default()
                 },
                 transform: Transform {
@@ -137,61 +327,105 @@ pub fn spawn_block(
                     ..default()
                 }
                 .with_rotation(Quat::from_rotation_z(
-                    (get_block_dir(block_type) as f32) * -3.1415926535 / 2.,
+                    (get_block_dir(block_type) as f32) * -PI / 2.,
                 )),
                 texture: get_block_texture(block_type, asset_server),
                 ..default()
             },
             BlockEntity {
-                pos: pos,
+                pos,
                 // block_type: block_type,
             },
         ))
         .id();
+    // chests start empty. ones loaded from a chunk get what was in them afterwards
+    if block_type == BlockType::Chest {
+        commands.entity(id).insert(ChestInventory::default());
+    }
 
     block_map.blocks.insert(pos, (id, block_type));
+    if matches!(block_type, BlockType::PressurePlate(_)) {
+        block_map.pressure_plates.insert(pos);
+    }
+    if block_type == BlockType::ChunkLoader {
+        block_map.chunk_loaders.insert(pos);
+    }
+    if matches!(block_type, BlockType::Inserter(..)) {
+        block_map.inserters.insert(pos);
+    }
     create_block_update(pos, block_update_queue);
 }
 
-pub fn get_block_type_from_string_rep(block_type: &str, data: &[&str]) -> Option<BlockType> {
+pub fn get_block_type_from_string_rep(
+    block_type: &str,
+    data: &[&str],
+) -> Result<BlockType, ParseErrorReason> {
     // dbg!(data);
     match block_type {
-        "wood" => Some(BlockType::Wood),
-        "stone" => Some(BlockType::Stone(if data.len() > 0 {
-            data[0].parse::<i32>().unwrap()
-        } else {
-            0
-        })),
-        "wire" => Some(BlockType::Wire(if data.len() > 0 {
-            data[0].parse::<i32>().unwrap()
-        } else {
-            0
-        })),
-        "repeater" => Some(BlockType::Repeater(
-            if data.len() > 0 {
-                data[0].parse::<i32>().unwrap()
-            } else {
-                0
-            },
-            if data.len() > 1 {
-                data[1].parse::<i32>().unwrap()
-            } else {
-                2
-            },
+        "wood" => Ok(BlockType::Wood),
+        "stone" => Ok(BlockType::Stone(parse_word_or(data, 0, 0)?)),
+        "wire" => Ok(BlockType::Wire(parse_word_or(data, 0, 0)?)),
+        "directional_wire" => Ok(BlockType::DirectionalWire(
+            parse_word_or(data, 0, 0)?,
+            parse_word_or(data, 1, 0)?,
         )),
-        "inverter" => Some(BlockType::Inverter(
-            if data.len() > 0 {
-                data[0].parse::<i32>().unwrap()
-            } else {
-                1
-            },
-            if data.len() > 1 {
-                data[1].parse::<i32>().unwrap()
-            } else {
-                2
-            },
+        "repeater" => Ok(BlockType::Repeater(
+            parse_word_or(data, 0, 0)?,
+            parse_word_or(data, 1, 2)?,
+            parse_word_or(data, 2, DEFAULT_PROCESSOR_DELAY)?,
         )),
-        _ => None,
+        "inverter" => Ok(BlockType::Inverter(
+            parse_word_or(data, 0, 1)?,
+            parse_word_or(data, 1, 2)?,
+            parse_word_or(data, 2, DEFAULT_PROCESSOR_DELAY)?,
+        )),
+        "lever" => Ok(BlockType::Lever(parse_word_or(data, 0, 0)?)),
+        "button" => Ok(BlockType::Button(parse_word_or(data, 0, 0)?)),
+        "pressure_plate" => Ok(BlockType::PressurePlate(parse_word_or(data, 0, 0)?)),
+        "lamp" => Ok(BlockType::Lamp(parse_word_or(data, 0, 0)?)),
+        "seven_segment_display" => Ok(BlockType::SevenSegmentDisplay(parse_word_or(data, 0, 0)?)),
+        "comparator" => Ok(BlockType::Comparator(
+            parse_word_or(data, 0, 0)?,
+            parse_word_or(data, 1, 2)?,
+            parse_word_or(data, 2, COMPARATOR_COMPARE_MODE)?,
+        )),
+        "sr_latch" => Ok(BlockType::SrLatch(
+            parse_word_or(data, 0, 0)?,
+            parse_word_or(data, 1, 2)?,
+            parse_word_or(data, 2, 0)?,
+        )),
+        "d_flip_flop" => Ok(BlockType::DFlipFlop(
+            parse_word_or(data, 0, 0)?,
+            parse_word_or(data, 1, 2)?,
+            parse_word_or(data, 2, 0)?,
+            parse_word_or(data, 3, 0)?,
+        )),
+        "chunk_loader" => Ok(BlockType::ChunkLoader),
+        "chest" => Ok(BlockType::Chest),
+        "conveyor" => Ok(BlockType::Conveyor(
+            parse_word_or(data, 0, 0)?,
+            parse_word_or(data, 1, 0)?,
+        )),
+        "inserter" => Ok(BlockType::Inserter(
+            parse_word_or(data, 0, 0)?,
+            parse_word_or(data, 1, 0)?,
+        )),
+        // the contents of the ram come after its power and direction, one word per cell
+        "ram" => Ok(BlockType::Ram(
+            parse_word_or(data, 0, 0)?,
+            parse_word_or(data, 1, 2)?,
+            get_ram_cells_from_string_rep(if data.len() > 2 { &data[2..] } else { &[] })?,
+        )),
+        _ => {
+            let gate_type = get_gate_type_from_string_rep(block_type)
+                .ok_or_else(|| ParseErrorReason::UnknownType(String::from(block_type)))?;
+            Ok(BlockType::Gate(
+                gate_type,
+                parse_word_or(data, 0, get_gate_output(gate_type, false, false) as i32)?,
+                parse_word_or(data, 1, 2)?,
+                parse_word_or(data, 2, DEFAULT_PROCESSOR_DELAY)?,
+            ))
+        }
     }
 }
 
@@ -201,56 +435,105 @@ pub fn get_string_rep_from_block_type(block_type: Option<BlockType>) -> (String,
             BlockType::Wood => (String::from("wood"), String::new()),
             BlockType::Stone(number) => (String::from("stone"), format!("{}", number)),
             BlockType::Wire(power) => (String::from("wire"), format!("{}", power)),
-            BlockType::Repeater(power, dir) => {
-                (String::from("repeater"), format!("{} {}", power, dir))
+            BlockType::DirectionalWire(power, dir) => (
+                String::from("directional_wire"),
+                format!("{} {}", power, dir),
+            ),
+            BlockType::Repeater(power, dir, delay) => (
+                String::from("repeater"),
+                format!("{} {} {}", power, dir, delay),
+            ),
+            BlockType::Inverter(power, dir, delay) => (
+                String::from("inverter"),
+                format!("{} {} {}", power, dir, delay),
+            ),
+            BlockType::Gate(gate_type, power, dir, delay) => (
+                String::from(get_string_rep_from_gate_type(gate_type)),
+                format!("{} {} {}", power, dir, delay),
+            ),
+            BlockType::Lever(power) => (String::from("lever"), format!("{}", power)),
+            BlockType::Button(power) => (String::from("button"), format!("{}", power)),
+            BlockType::PressurePlate(power) => {
+                (String::from("pressure_plate"), format!("{}", power))
             }
-            BlockType::Inverter(power, dir) => {
-                (String::from("inverter"), format!("{} {}", power, dir))
+            BlockType::Lamp(power) => (String::from("lamp"), format!("{}", power)),
+            BlockType::SevenSegmentDisplay(value) => {
+                (String::from("seven_segment_display"), format!("{}", value))
+            }
+            BlockType::Comparator(power, dir, mode) => (
+                String::from("comparator"),
+                format!("{} {} {}", power, dir, mode),
+            ),
+            BlockType::SrLatch(power, dir, state) => (
+                String::from("sr_latch"),
+                format!("{} {} {}", power, dir, state),
+            ),
+            BlockType::DFlipFlop(power, dir, clock, state) => (
+                String::from("d_flip_flop"),
+                format!("{} {} {} {}", power, dir, clock, state),
+            ),
+            BlockType::ChunkLoader => (String::from("chunk_loader"), String::new()),
+            BlockType::Chest => (String::from("chest"), String::new()),
+            BlockType::Conveyor(power, dir) => {
+                (String::from("conveyor"), format!("{} {}", power, dir))
             }
+            BlockType::Inserter(power, dir) => {
+                (String::from("inserter"), format!("{} {}", power, dir))
+            }
+            BlockType::Ram(power, dir, cells) => (
+                String::from("ram"),
+                format!(
+                    "{} {} {}",
+                    power,
+                    dir,
+                    get_string_rep_from_ram_cells(&cells)
+                ),
+            ),
         },
         None => (String::from("nothing"), String::new()),
     }
 }
 
-pub fn get_item_type_from_string_rep(item_type: &str, data: &[&str]) -> Option<ItemType> {
+pub fn get_item_type_from_string_rep(
+    item_type: &str,
+    data: &[&str],
+) -> Result<ItemType, ParseErrorReason> {
     // dbg!(data);
     match item_type {
-        "wood" => Some(ItemType::Wood),
-        "stone" => Some(ItemType::Stone(if data.len() > 0 {
-            data[0].parse::<i32>().unwrap()
-        } else {
-            0
-        })),
-        "wire" => Some(ItemType::Wire(if data.len() > 0 {
-            data[0].parse::<i32>().unwrap()
-        } else {
-            0
-        })),
-        "repeater" => Some(ItemType::Repeater(
-            if data.len() > 0 {
-                data[0].parse::<i32>().unwrap()
-            } else {
-                0
-            },
-            if data.len() > 1 {
-                data[1].parse::<i32>().unwrap()
-            } else {
-                2
-            },
+        "wood" => Ok(ItemType::Wood),
+        "stone" => Ok(ItemType::Stone(parse_word_or(data, 0, 0)?)),
+        "wire" => Ok(ItemType::Wire(parse_word_or(data, 0, 0)?)),
+        "directional_wire" => Ok(ItemType::DirectionalWire(
+            parse_word_or(data, 0, 0)?,
+            parse_word_or(data, 1, 0)?,
         )),
-        "inverter" => Some(ItemType::Inverter(
-            if data.len() > 0 {
-                data[0].parse::<i32>().unwrap()
-            } else {
-                1
-            },
-            if data.len() > 1 {
-                data[1].parse::<i32>().unwrap()
-            } else {
-                2
-            },
+        "repeater" => Ok(ItemType::Repeater(
+            parse_word_or(data, 0, 0)?,
+            parse_word_or(data, 1, 2)?,
         )),
-        _ => None,
+        "inverter" => Ok(ItemType::Inverter(
+            parse_word_or(data, 0, 1)?,
+            parse_word_or(data, 1, 2)?,
+        )),
+        "lever" => Ok(ItemType::Lever),
+        "button" => Ok(ItemType::Button),
+        "pressure_plate" => Ok(ItemType::PressurePlate),
+        "lamp" => Ok(ItemType::Lamp),
+        "seven_segment_display" => Ok(ItemType::SevenSegmentDisplay),
+        "comparator" => Ok(ItemType::Comparator),
+        "sr_latch" => Ok(ItemType::SrLatch),
+        "d_flip_flop" => Ok(ItemType::DFlipFlop),
+        "ram" => Ok(ItemType::Ram),
+        "chunk_loader" => Ok(ItemType::ChunkLoader),
+        "chest" => Ok(ItemType::Chest),
+        "conveyor" => Ok(ItemType::Conveyor),
+        "inserter" => Ok(ItemType::Inserter),
+        "copper" => Ok(ItemType::Copper),
+        "iron" => Ok(ItemType::Iron),
+        "redstone_dust" => Ok(ItemType::RedstoneDust),
+        _ => get_gate_type_from_string_rep(item_type)
+            .map(ItemType::Gate)
+            .ok_or_else(|| ParseErrorReason::UnknownType(String::from(item_type))),
     }
 }
 
@@ -260,17 +543,76 @@ pub fn get_string_rep_from_item_type(item_type: Option<ItemType>) -> (String, St
             ItemType::Wood => (String::from("wood"), String::new()),
             ItemType::Stone(number) => (String::from("stone"), format!("{}", number)),
             ItemType::Wire(power) => (String::from("wire"), format!("{}", power)),
+            ItemType::DirectionalWire(power, dir) => (
+                String::from("directional_wire"),
+                format!("{} {}", power, dir),
+            ),
             ItemType::Repeater(power, dir) => {
                 (String::from("repeater"), format!("{} {}", power, dir))
             }
             ItemType::Inverter(power, dir) => {
                 (String::from("inverter"), format!("{} {}", power, dir))
             }
+            ItemType::Gate(gate_type) => (
+                String::from(get_string_rep_from_gate_type(gate_type)),
+                String::new(),
+            ),
+            ItemType::Lever => (String::from("lever"), String::new()),
+            ItemType::Button => (String::from("button"), String::new()),
+            ItemType::PressurePlate => (String::from("pressure_plate"), String::new()),
+            ItemType::Lamp => (String::from("lamp"), String::new()),
+            ItemType::SevenSegmentDisplay => (String::from("seven_segment_display"), String::new()),
+            ItemType::Comparator => (String::from("comparator"), String::new()),
+            ItemType::SrLatch => (String::from("sr_latch"), String::new()),
+            ItemType::DFlipFlop => (String::from("d_flip_flop"), String::new()),
+            ItemType::Ram => (String::from("ram"), String::new()),
+            ItemType::ChunkLoader => (String::from("chunk_loader"), String::new()),
+            ItemType::Chest => (String::from("chest"), String::new()),
+            ItemType::Conveyor => (String::from("conveyor"), String::new()),
+            ItemType::Inserter => (String::from("inserter"), String::new()),
+            ItemType::Copper => (String::from("copper"), String::new()),
+            ItemType::Iron => (String::from("iron"), String::new()),
+            ItemType::RedstoneDust => (String::from("redstone_dust"), String::new()),
         },
         None => (String::from("nothing"), String::new()),
     }
 }
 
+pub fn get_ram_cells_from_string_rep(data: &[&str]) -> Result<[u8; RAM_SIZE], ParseErrorReason> {
+    let mut cells = [0; RAM_SIZE];
+    for (i, cell) in cells.iter_mut().enumerate() {
+        *cell = parse_word_or(data, i, 0)?;
+    }
+    Ok(cells)
+}
+
+pub fn get_string_rep_from_ram_cells(cells: &[u8; RAM_SIZE]) -> String {
+    cells
+        .iter()
+        .map(|cell| cell.to_string())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+pub fn get_gate_type_from_string_rep(gate_type: &str) -> Option<GateType> {
+    match gate_type {
+        "and_gate" => Some(GateType::And),
+        "or_gate" => Some(GateType::Or),
+        "xor_gate" => Some(GateType::Xor),
+        "nand_gate" => Some(GateType::Nand),
+        _ => None,
+    }
+}
+
+pub fn get_string_rep_from_gate_type(gate_type: GateType) -> &'static str {
+    match gate_type {
+        GateType::And => "and_gate",
+        GateType::Or => "or_gate",
+        GateType::Xor => "xor_gate",
+        GateType::Nand => "nand_gate",
+    }
+}
+
 pub fn get_block_rep_from_string_rep_and_pos(
     string_rep: (String, String),
     block_pos: (i32, i32),
@@ -280,22 +622,194 @@ pub fn get_block_rep_from_string_rep_and_pos(
         string_rep.0, block_pos.0, block_pos.1, string_rep.1
     )
 }
+// the data a block keeps when it's broken and put in the inventory, if it has any worth keeping.
+// ram keeps its cells, so a programmed ram block can be moved
+pub fn get_item_data_from_block_type(block_type: BlockType) -> Option<String> {
+    match block_type {
+        BlockType::Ram(_, _, cells) if cells.iter().any(|cell| *cell != 0) => {
+            Some(get_string_rep_from_ram_cells(&cells))
+        }
+        _ => None,
+    }
+}
+
+// puts the data an item kept from get_item_data_from_block_type back into the block it's placed as
+pub fn get_block_type_with_item_data(block_type: BlockType, data: &str) -> BlockType {
+    match block_type {
+        BlockType::Ram(power, dir, _) => {
+            let parts = data.split(' ').collect::<Vec<&str>>();
+            BlockType::Ram(
+                power,
+                dir,
+                get_ram_cells_from_string_rep(&parts).unwrap_or([0; RAM_SIZE]),
+            )
+        }
+        _ => block_type,
+    }
+}
+
 pub fn remove_block_type_data_for_inventory(block_type: BlockType) -> ItemType {
     match block_type {
         BlockType::Stone(_) => ItemType::Stone(0),
         BlockType::Wire(_) => ItemType::Wire(0),
-        BlockType::Repeater(_, _) => ItemType::Repeater(0, 2),
-        BlockType::Inverter(_, _) => ItemType::Inverter(1, 2),
+        BlockType::DirectionalWire(_, _) => ItemType::DirectionalWire(0, 0),
+        BlockType::Repeater(..) => ItemType::Repeater(0, 2),
+        BlockType::Inverter(..) => ItemType::Inverter(1, 2),
+        BlockType::Gate(gate_type, ..) => ItemType::Gate(gate_type),
+        BlockType::Lever(_) => ItemType::Lever,
+        BlockType::Button(_) => ItemType::Button,
+        BlockType::PressurePlate(_) => ItemType::PressurePlate,
+        BlockType::Lamp(_) => ItemType::Lamp,
+        BlockType::SevenSegmentDisplay(_) => ItemType::SevenSegmentDisplay,
+        BlockType::Comparator(..) => ItemType::Comparator,
+        BlockType::SrLatch(..) => ItemType::SrLatch,
+        BlockType::DFlipFlop(..) => ItemType::DFlipFlop,
+        BlockType::Ram(..) => ItemType::Ram,
+        BlockType::ChunkLoader => ItemType::ChunkLoader,
+        BlockType::Chest => ItemType::Chest,
+        BlockType::Conveyor(..) => ItemType::Conveyor,
+        BlockType::Inserter(..) => ItemType::Inserter,
         BlockType::Wood => ItemType::Wood,
     }
 }
 
-pub fn get_block_type_after_popped_from_inventory(item_type: ItemType, dir: i32) -> BlockType {
-    match item_type {
-        ItemType::Repeater(power, _) => BlockType::Repeater(power, (dir + 2) % 4),
-        ItemType::Inverter(power, _) => BlockType::Inverter(power, (dir + 2) % 4),
+// returns None for items that aren't blocks, so they can't be placed
+pub fn get_block_type_after_popped_from_inventory(
+    item_type: ItemType,
+    dir: i32,
+) -> Option<BlockType> {
+    let block_type = match item_type {
+        ItemType::Repeater(power, _) => {
+            BlockType::Repeater(power, (dir + 2) % 4, DEFAULT_PROCESSOR_DELAY)
+        }
+        ItemType::Inverter(power, _) => {
+            BlockType::Inverter(power, (dir + 2) % 4, DEFAULT_PROCESSOR_DELAY)
+        }
+        ItemType::Gate(gate_type) => BlockType::Gate(
+            gate_type,
+            get_gate_output(gate_type, false, false) as i32,
+            (dir + 2) % 4,
+            DEFAULT_PROCESSOR_DELAY,
+        ),
+        ItemType::Lever => BlockType::Lever(0),
+        ItemType::Button => BlockType::Button(0),
+        ItemType::PressurePlate => BlockType::PressurePlate(0),
+        ItemType::Lamp => BlockType::Lamp(0),
+        ItemType::SevenSegmentDisplay => BlockType::SevenSegmentDisplay(0),
+        ItemType::Comparator => BlockType::Comparator(0, (dir + 2) % 4, COMPARATOR_COMPARE_MODE),
+        ItemType::SrLatch => BlockType::SrLatch(0, (dir + 2) % 4, 0),
+        ItemType::DFlipFlop => BlockType::DFlipFlop(0, (dir + 2) % 4, 0, 0),
+        ItemType::Ram => BlockType::Ram(0, (dir + 2) % 4, [0; RAM_SIZE]),
+        ItemType::ChunkLoader => BlockType::ChunkLoader,
+        ItemType::Chest => BlockType::Chest,
+        // unlike repeaters, these face away from the player, so things are carried away from them
+        ItemType::Conveyor => BlockType::Conveyor(0, dir),
+        ItemType::Inserter => BlockType::Inserter(0, dir),
         ItemType::Stone(a) => BlockType::Stone(a),
         ItemType::Wire(power) => BlockType::Wire(power),
+        ItemType::DirectionalWire(power, _) => BlockType::DirectionalWire(power, dir),
         ItemType::Wood => BlockType::Wood,
+        ItemType::Copper | ItemType::Iron | ItemType::RedstoneDust => return None,
+    };
+    Some(block_type)
+}
+
+// returns the block with its delay turned up by one tick, wrapping back around to one tick.
+// only repeaters, inverters and gates have a delay
+pub fn get_block_type_with_next_delay(block_type: BlockType) -> Option<BlockType> {
+    match block_type {
+        BlockType::Repeater(power, dir, delay) => Some(BlockType::Repeater(
+            power,
+            dir,
+            delay % MAX_PROCESSOR_DELAY + 1,
+        )),
+        BlockType::Inverter(power, dir, delay) => Some(BlockType::Inverter(
+            power,
+            dir,
+            delay % MAX_PROCESSOR_DELAY + 1,
+        )),
+        BlockType::Gate(gate_type, power, dir, delay) => Some(BlockType::Gate(
+            gate_type,
+            power,
+            dir,
+            delay % MAX_PROCESSOR_DELAY + 1,
+        )),
+        _ => None,
+    }
+}
+
+// returns the block after the player right clicks on it. levers toggle, buttons turn on,
+// comparators switch mode and everything with a delay has its delay turned up. returns None if
+// nothing happens
+pub fn get_block_type_after_use(block_type: BlockType) -> Option<BlockType> {
+    match block_type {
+        BlockType::Lever(power) => Some(BlockType::Lever(1 - power)),
+        BlockType::Button(_) => Some(BlockType::Button(1)),
+        BlockType::Comparator(power, dir, mode) => {
+            Some(BlockType::Comparator(power, dir, 1 - mode))
+        }
+        _ => get_block_type_with_next_delay(block_type),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{run_logic_tick, BlockGrid, UpdateQueue, BUTTON_PRESS_TICKS};
+
+    type Grid = HashMap<(i32, i32), BlockType>;
+
+    // right clicks the block at pos, the way block_placer_breaker_system does
+    fn use_block(grid: &mut Grid, updates: &mut UpdateQueue, pos: (i32, i32)) {
+        let block_type = grid.get_block(pos).unwrap();
+        grid.set_block(pos, get_block_type_after_use(block_type).unwrap());
+        updates.create_block_update(pos);
+    }
+
+    fn run_ticks(grid: &mut Grid, updates: &mut UpdateQueue, ticks: i32) {
+        for _ in 0..ticks {
+            run_logic_tick(grid, updates);
+        }
+    }
+
+    // a lever powers the wire next to it from the first use until the next one
+    #[test]
+    fn lever_stays_on_until_used_again() {
+        let mut grid = Grid::new();
+        let mut updates = UpdateQueue::default();
+        grid.set_block((0, 0), BlockType::Lever(0));
+        grid.set_block((1, 0), BlockType::Wire(0));
+
+        use_block(&mut grid, &mut updates, (0, 0));
+        run_ticks(&mut grid, &mut updates, 1);
+        assert_eq!(grid.get_block((1, 0)), Some(BlockType::Wire(128)));
+        run_ticks(&mut grid, &mut updates, 100);
+        assert_eq!(grid.get_block((0, 0)), Some(BlockType::Lever(1)));
+        assert_eq!(grid.get_block((1, 0)), Some(BlockType::Wire(128)));
+
+        use_block(&mut grid, &mut updates, (0, 0));
+        run_ticks(&mut grid, &mut updates, 1);
+        assert_eq!(grid.get_block((0, 0)), Some(BlockType::Lever(0)));
+        assert_eq!(grid.get_block((1, 0)), Some(BlockType::Wire(0)));
+    }
+
+    // a button powers the wire next to it for BUTTON_PRESS_TICKS ticks, then lets go by itself
+    #[test]
+    fn button_releases_after_its_press_ticks() {
+        let mut grid = Grid::new();
+        let mut updates = UpdateQueue::default();
+        grid.set_block((0, 0), BlockType::Button(0));
+        grid.set_block((1, 0), BlockType::Wire(0));
+
+        use_block(&mut grid, &mut updates, (0, 0));
+        run_ticks(&mut grid, &mut updates, BUTTON_PRESS_TICKS);
+        assert_eq!(grid.get_block((0, 0)), Some(BlockType::Button(1)));
+        assert_eq!(grid.get_block((1, 0)), Some(BlockType::Wire(128)));
+
+        run_ticks(&mut grid, &mut updates, 1);
+        assert_eq!(grid.get_block((0, 0)), Some(BlockType::Button(0)));
+        assert_eq!(grid.get_block((1, 0)), Some(BlockType::Wire(0)));
     }
 }
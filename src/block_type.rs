@@ -0,0 +1,52 @@
+// the kinds of blocks there are, and the numbers that decide how they behave in circuits. this is
+// kept apart from block.rs, which draws and spawns them, so the circuit simulation doesn't need bevy
+
+#[derive(PartialEq, Hash, Eq, Clone, Copy, Debug)]
+pub enum BlockType {
+    Wood,
+    Stone(i32),
+    Wire(i32),
+    DirectionalWire(i32, i32),
+    Repeater(i32, i32, i32),
+    Inverter(i32, i32, i32),
+    Gate(GateType, i32, i32, i32),
+    Lever(i32),
+    Button(i32),
+    PressurePlate(i32),
+    Lamp(i32),
+    SevenSegmentDisplay(i32),
+    Comparator(i32, i32, i32),
+    SrLatch(i32, i32, i32),
+    DFlipFlop(i32, i32, i32, i32),
+    Ram(i32, i32, [u8; RAM_SIZE]),
+    ChunkLoader,
+    // what's in a chest is kept in the ChestInventory on its entity
+    Chest,
+    // conveyors carry items on the ground along the way they're facing, and inserters move one
+    // item at a time from behind them to in front of them. both stop while they're powered
+    Conveyor(i32, i32),
+    Inserter(i32, i32),
+}
+
+// gates take their inputs from both sides and output in front, like a repeater
+#[derive(PartialEq, Hash, Eq, Clone, Copy, Debug)]
+pub enum GateType {
+    And,
+    Or,
+    Xor,
+    Nand,
+}
+
+// the number of logic ticks a repeater, inverter or gate takes to switch, and how far it can be turned up
+pub const DEFAULT_PROCESSOR_DELAY: i32 = 1;
+pub const MAX_PROCESSOR_DELAY: i32 = 4;
+// comparators either pass on their rear signal if it's at least as strong as the side signals,
+// or subtract the side signals from it
+pub const COMPARATOR_COMPARE_MODE: i32 = 0;
+pub const COMPARATOR_SUBTRACT_MODE: i32 = 1;
+// the number of cells in a ram block, and how much the signal strength on its address input has
+// to go up by to select the next cell
+pub const RAM_SIZE: usize = 16;
+pub const RAM_ADDRESS_STEP: i32 = 8;
+// how many logic ticks a button stays on for after being pressed
+pub const BUTTON_PRESS_TICKS: i32 = 20;
@@ -0,0 +1,80 @@
+use crate::{
+    get_biome, get_chunk_rng, parse_word, Biome, Collectible, ItemType, ParseErrorReason,
+    StaticCollisionCircle, BOULDER_SALT,
+};
+use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
+use rand::Rng;
+
+// a big rock that only shows up in rocky hills. it takes a while to break, but gives a lot of stone
+#[derive(Component)]
+pub struct Boulder {}
+
+pub fn spawn_boulder(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    pos: Vec3,
+    uses: usize,
+) {
+    let normal_material = materials.add(ColorMaterial::from(Color::rgb(0.3, 0.3, 0.32)));
+    let punched_material = materials.add(ColorMaterial::from(Color::rgb(0.5, 0.5, 0.52)));
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: meshes.add(shape::Circle::new(1.6).into()).into(),
+            material: materials.add(ColorMaterial::from(Color::rgb(0.3, 0.3, 0.32))),
+            transform: Transform::from_translation(pos),
+            ..default()
+        },
+        Boulder {},
+        Collectible {
+            pos: Vec2::new(pos.x, pos.y),
+            gather_radius: 3.5,
+            item_type: ItemType::Stone(0),
+            uses,
+            normal_material,
+            punched_material,
+        },
+        StaticCollisionCircle { radius: 1.6 },
+    ));
+}
+
+pub fn make_string_rep_of_boulder(collectible: &Collectible) -> String {
+    format!(
+        "boulder {} {} {}\n",
+        collectible.pos.x, collectible.pos.y, collectible.uses
+    )
+}
+
+pub fn spawn_boulder_from_string_rep(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    parts: Vec<&str>, // a vector of strings, containing the words in the string representation
+) -> Result<(), ParseErrorReason> {
+    let x = parse_word::<f32>(&parts, 1)?;
+    let y = parse_word::<f32>(&parts, 2)?;
+    let uses = parse_word::<usize>(&parts, 3)?;
+    spawn_boulder(commands, meshes, materials, Vec3::new(x, y, 0.07), uses);
+    Ok(())
+}
+
+pub fn generate_boulders(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    pos: (i32, i32),
+    seed: u64,
+) {
+    let mut rng = get_chunk_rng(seed, pos, BOULDER_SALT);
+    for _ in 0..4 {
+        let pos = Vec3::new(
+            ((rng.gen::<f32>()) + pos.0 as f32) * 16.,
+            ((rng.gen::<f32>()) + pos.1 as f32) * 16.,
+            0.07,
+        );
+        if rng.gen::<f32>() >= 0.3 || get_biome(seed, pos.truncate()) != Biome::RockyHills {
+            continue;
+        }
+        spawn_boulder(commands, meshes, materials, pos, 25);
+    }
+}
@@ -0,0 +1,81 @@
+use crate::{
+    get_biome, get_chunk_rng, parse_word, Biome, Collectible, ItemType, ParseErrorReason,
+    StaticCollisionCircle, CACTUS_SALT,
+};
+use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
+use rand::Rng;
+
+// grows in deserts, where there aren't any trees. it's smaller than a tree, so it runs out of wood
+// sooner
+#[derive(Component)]
+pub struct Cactus {}
+
+pub fn spawn_cactus(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    pos: Vec3,
+    uses: usize,
+) {
+    let normal_material = materials.add(ColorMaterial::from(Color::rgb(0.25, 0.55, 0.3)));
+    let punched_material = materials.add(ColorMaterial::from(Color::rgb(0.5, 0.75, 0.5)));
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: meshes.add(shape::Circle::new(0.6).into()).into(),
+            material: materials.add(ColorMaterial::from(Color::rgb(0.25, 0.55, 0.3))),
+            transform: Transform::from_translation(pos),
+            ..default()
+        },
+        Cactus {},
+        Collectible {
+            pos: Vec2::new(pos.x, pos.y),
+            gather_radius: 2.5,
+            item_type: ItemType::Wood,
+            uses,
+            normal_material,
+            punched_material,
+        },
+        StaticCollisionCircle { radius: 0.6 },
+    ));
+}
+
+pub fn make_string_rep_of_cactus(collectible: &Collectible) -> String {
+    format!(
+        "cactus {} {} {}\n",
+        collectible.pos.x, collectible.pos.y, collectible.uses
+    )
+}
+
+pub fn spawn_cactus_from_string_rep(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    parts: Vec<&str>, // a vector of strings, containing the words in the string representation
+) -> Result<(), ParseErrorReason> {
+    let x = parse_word::<f32>(&parts, 1)?;
+    let y = parse_word::<f32>(&parts, 2)?;
+    let uses = parse_word::<usize>(&parts, 3)?;
+    spawn_cactus(commands, meshes, materials, Vec3::new(x, y, 0.06), uses);
+    Ok(())
+}
+
+pub fn generate_cacti(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    pos: (i32, i32),
+    seed: u64,
+) {
+    let mut rng = get_chunk_rng(seed, pos, CACTUS_SALT);
+    for _ in 0..8 {
+        let pos = Vec3::new(
+            ((rng.gen::<f32>()) + pos.0 as f32) * 16.,
+            ((rng.gen::<f32>()) + pos.1 as f32) * 16.,
+            0.06,
+        );
+        if rng.gen::<f32>() >= 0.25 || get_biome(seed, pos.truncate()) != Biome::Desert {
+            continue;
+        }
+        spawn_cactus(commands, meshes, materials, pos, 4);
+    }
+}
@@ -0,0 +1,156 @@
+use bevy::prelude::*;
+
+use crate::{
+    get_slot_from_string_rep, make_string_rep_of_slot, parse_word, spawn_dropped_item,
+    InventorySlot, ParseErrorReason,
+};
+
+// how many slots a chest has, 3 rows of 9
+pub const CHEST_SIZE: usize = 27;
+
+// the items in a chest block. it's kept on the block's entity, since BlockType has to stay Copy
+#[derive(Component, Clone)]
+pub struct ChestInventory {
+    pub slots: Vec<Option<InventorySlot>>,
+}
+
+impl Default for ChestInventory {
+    fn default() -> Self {
+        ChestInventory {
+            slots: vec![None; CHEST_SIZE],
+        }
+    }
+}
+
+// every slot that has something in it gets its own line after the chest's block line, like
+// "chest_slot x y index wire 5"
+pub fn make_string_rep_of_chest_contents(pos: (i32, i32), chest: &ChestInventory) -> String {
+    let mut chest_string = String::new();
+    for (i, slot) in chest.slots.iter().enumerate() {
+        if slot.is_some() {
+            chest_string.push_str(
+                format!(
+                    "chest_slot {} {} {} {}\n",
+                    pos.0,
+                    pos.1,
+                    i,
+                    make_string_rep_of_slot(slot)
+                )
+                .as_str(),
+            );
+        }
+    }
+    chest_string
+}
+
+// the position of the chest a chest_slot line is for, the index of the slot and what's in it
+type ChestSlotLine = ((i32, i32), usize, Option<InventorySlot>);
+
+// reads which chest a chest_slot line is for, which slot it is and what's in it
+pub fn get_chest_slot_from_string_rep(parts: &[&str]) -> Result<ChestSlotLine, ParseErrorReason> {
+    let x = parse_word::<i32>(parts, 1)?;
+    let y = parse_word::<i32>(parts, 2)?;
+    let index = parse_word::<usize>(parts, 3)?;
+    if index >= CHEST_SIZE {
+        return Err(ParseErrorReason::BadWord(String::from(parts[3])));
+    }
+    // get_slot_from_string_rep skips the first word it's given, so the slice starts at the index
+    // to line the item's name up with where it reads it from
+    let slot = get_slot_from_string_rep(&parts[3..])?;
+    Ok(((x, y), index, slot))
+}
+
+// puts one of an item in a chest, on a stack of it if there is one. returns false if it's full
+pub fn insert_one_into_chest(chest: &mut ChestInventory, item: &InventorySlot) -> bool {
+    if let Some(slot) =
+        chest.slots.iter_mut().flatten().find(|slot| {
+            slot.can_stack_with(item.item_type, &item.data) && slot.count < slot.max_stack
+        })
+    {
+        slot.count += 1;
+        return true;
+    }
+    if let Some(slot) = chest.slots.iter_mut().find(|slot| slot.is_none()) {
+        *slot = Some(InventorySlot {
+            count: 1,
+            ..item.clone()
+        });
+        return true;
+    }
+    false
+}
+
+// takes one of an item out of the first stack of it in a chest
+pub fn take_one_from_chest(chest: &mut ChestInventory, item: &InventorySlot) {
+    for slot in chest.slots.iter_mut() {
+        let Some(item_slot) = slot else {
+            continue;
+        };
+        if item_slot.can_stack_with(item.item_type, &item.data) {
+            item_slot.count -= 1;
+            if item_slot.count == 0 {
+                *slot = None;
+            }
+            return;
+        }
+    }
+}
+
+// when a chest is broken, everything in it falls out where it was
+pub fn drop_chest_contents(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    pos: (i32, i32),
+    chest: &ChestInventory,
+) {
+    for slot in chest.slots.iter().flatten() {
+        spawn_dropped_item(
+            commands,
+            asset_server,
+            Vec2::new(pos.0 as f32, pos.1 as f32),
+            slot.clone(),
+            0.,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ItemType;
+
+    // each chest_slot line reads back as the same slot, including data with spaces in it
+    #[test]
+    fn chest_slot_lines_round_trip() {
+        let mut chest = ChestInventory::default();
+        chest.slots[0] = Some(InventorySlot::new(ItemType::Wood, 5, None));
+        chest.slots[26] = Some(InventorySlot::new(
+            ItemType::Ram,
+            1,
+            Some(String::from("1 2 3")),
+        ));
+        let string_rep = make_string_rep_of_chest_contents((-3, 7), &chest);
+        assert_eq!(
+            string_rep,
+            "chest_slot -3 7 0 wood 5\nchest_slot -3 7 26 ram 1 1 2 3\n"
+        );
+
+        let mut read_back = ChestInventory::default();
+        for line in string_rep.lines() {
+            let parts = line.split(' ').collect::<Vec<&str>>();
+            let (pos, index, slot) = get_chest_slot_from_string_rep(&parts).unwrap();
+            assert_eq!(pos, (-3, 7));
+            read_back.slots[index] = slot;
+        }
+        assert_eq!(read_back.slots, chest.slots);
+    }
+
+    #[test]
+    fn chest_slot_index_past_the_end_is_an_error() {
+        let parts = ["chest_slot", "0", "0", "27", "wood", "5"];
+        assert_eq!(
+            get_chest_slot_from_string_rep(&parts),
+            Err(ParseErrorReason::BadWord(String::from("27")))
+        );
+    }
+}
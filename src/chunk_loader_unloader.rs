@@ -9,26 +9,62 @@ use std::{
 pub struct ChunksToSave {
     pub chunks: HashMap<(i32, i32), String>,
     pub chunks_loaded: HashSet<(i32, i32)>,
+    // chunks that are out of render range, but whose blocks are still in the map and being
+    // simulated because the chunk has a ticket. their blocks are hidden instead of unloaded
+    pub chunks_simulated: HashSet<(i32, i32)>,
 }
 
 #[derive(Component)]
 pub struct ChunkBackground {}
 
 use crate::{
+    backup_damaged_file, generate_boulders, generate_cacti, generate_ground, generate_ores,
     generate_rocks, generate_trees, get_block_rep_from_string_rep_and_pos,
-    get_block_type_from_string_rep, get_item_type_from_string_rep, get_string_rep_from_block_type,
-    get_string_rep_from_item_type, insert_block_to_inventory, make_string_rep_of_rock,
-    make_string_rep_of_tree, spawn_block, spawn_rock_from_string_rep, spawn_tree_from_string_rep,
-    BlockEntity, BlockUpdateQueue, Collectible, CollisionBox, Map, PhysicsBody, Player,
-    PlayerInventory, Rock, Tree,
+    get_block_type_from_string_rep, get_chest_slot_from_string_rep, get_chest_to_save,
+    get_ground_from_string_rep, get_inventory_slots_to_save, get_item_type_from_string_rep,
+    get_region_chunk_name, get_region_of_chunk, get_regions_dir, get_string_rep_from_block_type,
+    get_string_rep_from_item_type, insert_block_to_inventory, make_string_rep_of_boulder,
+    make_string_rep_of_cactus, make_string_rep_of_chest_contents, make_string_rep_of_dropped_item,
+    make_string_rep_of_ground, make_string_rep_of_ore, make_string_rep_of_rock,
+    make_string_rep_of_tree, pack_chunk, parse_word, read_region, read_region_chunk, spawn_block,
+    spawn_boulder_from_string_rep, spawn_cactus_from_string_rep,
+    spawn_dropped_item_from_string_rep, spawn_ground, spawn_ore_from_string_rep,
+    spawn_rock_from_string_rep, spawn_tree_from_string_rep, unpack_chunk, write_file_atomically,
+    write_region, AppState, BlockEntity, BlockType, BlockUpdateQueue, Boulder, Cactus,
+    ChestInventory, Collectible, CollisionBox, CurrentWorld, DroppedItem, GroundMap,
+    InventoryScreen, InventorySlot, LoadErrors, Map, Ore, ParseError, ParseErrorReason,
+    PhysicsBody, Player, PlayerInventory, Rock, Tree, UpdateQueue, DEFAULT_PROCESSOR_DELAY,
+    INVENTORY_SIZE,
 };
 
 const RENDER_RADIUS: i32 = 2;
+// chunk loaders keep the chunks in a square of side 2*CHUNK_LOADER_RADIUS + 1 around them simulated
+const CHUNK_LOADER_RADIUS: i32 = 1;
+// the version of the chunk file format. bump this whenever the way anything in a chunk is saved
+// changes, and add a step to migrate_chunk_lines that upgrades files from the previous version
+pub const CHUNK_FORMAT_VERSION: u32 = 6;
+// the kinds of lines counted in a chunk file's header, and the format version they were added in.
+// a header only counts the kinds that were around in its version, so older headers still match
+const COUNTED_LINE_KINDS: [(&str, u32); 11] = [
+    ("block", 0),
+    ("tree", 0),
+    ("rock", 0),
+    ("cactus", 2),
+    ("boulder", 2),
+    ("ground", 3),
+    ("copper_ore", 4),
+    ("iron_ore", 4),
+    ("redstone_ore", 4),
+    ("item", 5),
+    ("chest_slot", 6),
+];
 
 // currently, there should only be one player
 pub fn save_players(
     players: Query<(&PhysicsBody, &Transform), With<Player>>,
     inventory: Res<PlayerInventory>,
+    inventory_screen: Res<InventoryScreen>,
+    current_world: Res<CurrentWorld>,
 ) {
     let mut players_string = String::new();
     for (physics_body, transform) in &players {
@@ -46,26 +82,41 @@ pub fn save_players(
         );
     }
 
-    for i in 0..inventory.slots.len() {
-        let mut block_type = None;
-        let mut count = 0;
-
-        match inventory.slots[i] {
-            Some(slot) => {
-                block_type = Some(slot.item_type);
-                count = slot.count;
-            }
-            None => {}
-        }
+    for slot in get_inventory_slots_to_save(&inventory, &inventory_screen).iter() {
+        players_string.push_str(format!("slot {}\n", make_string_rep_of_slot(slot)).as_str());
+    }
 
-        players_string.push_str("slot ");
-        players_string.push_str(get_string_rep_from_item_type(block_type).0.as_str());
-        players_string.push(' ');
-        players_string.push_str(count.to_string().as_str());
-        players_string.push('\n');
+    if let Err(error) = write_file_atomically(&current_world.get_players_file(), players_string) {
+        println!("error saving players: {}", error);
     }
+}
 
-    fs::write("./assets/players.txt", players_string).expect("Could not save players!");
+pub fn spawn_player(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    pos: Vec2,
+    vel: Vec2,
+    acc: Vec2,
+) {
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: meshes.add(shape::Circle::new(0.5).into()).into(),
+            material: materials.add(ColorMaterial::from(Color::rgb(0.9, 0.6, 0.3))),
+            transform: Transform::from_xyz(pos.x, pos.y, 0.05),
+            ..default()
+        },
+        Player {
+            break_cooldown: 0.,
+            attack_cooldown: 0.,
+            place_cooldown: 0.,
+        },
+        CollisionBox {
+            width: 0.77,
+            height: 0.77,
+        },
+        PhysicsBody { vel, acc },
+    ));
 }
 
 pub fn spawn_players(
@@ -73,60 +124,130 @@ pub fn spawn_players(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
     mut inventory: ResMut<PlayerInventory>,
+    mut load_errors: ResMut<LoadErrors>,
+    current_world: Res<CurrentWorld>,
 ) {
-    if let Ok(players_string) = fs::read_to_string("./assets/players.txt") {
+    let file = current_world.get_players_file();
+    // new worlds don't have a players file yet, so the player starts in the middle
+    let mut player_spawned = false;
+    if let Ok(players_string) = fs::read_to_string(&file) {
+        let mut errors = Vec::new();
         let lines = players_string.split('\n').collect::<Vec<&str>>();
-        for line in lines {
+        for (i, line) in lines.iter().enumerate() {
             let parts = line.split(' ').collect::<Vec<&str>>();
-            match parts[0] {
+            let result = match parts[0] {
                 "pos" => {
-                    let x = parts[1].parse::<f32>().unwrap();
-                    let y = parts[2].parse::<f32>().unwrap();
-                    let vel_x = parts[3].parse::<f32>().unwrap();
-                    let vel_y = parts[4].parse::<f32>().unwrap();
-                    let acc_x = parts[5].parse::<f32>().unwrap();
-                    let acc_y = parts[6].parse::<f32>().unwrap();
-
-                    commands.spawn((
-                        MaterialMesh2dBundle {
-                            mesh: meshes.add(shape::Circle::new(0.5).into()).into(),
-                            material: materials.add(ColorMaterial::from(Color::rgb(0.9, 0.6, 0.3))),
-                            transform: Transform::from_xyz(x, y, 0.05),
-                            ..default()
-                        },
-                        Player {
-                            break_cooldown: 0.,
-                            attack_cooldown: 0.,
-                            place_cooldown: 0.,
-                        },
-                        CollisionBox {
-                            width: 0.77,
-                            height: 0.77,
-                        },
-                        PhysicsBody {
-                            vel: Vec2::new(vel_x, vel_y),
-                            acc: Vec2::new(acc_x, acc_y),
-                        },
-                    ));
+                    // if the position can't be read, the player still has to be spawned somewhere
+                    let values = (1..7)
+                        .map(|i| parse_word::<f32>(&parts, i))
+                        .collect::<Result<Vec<f32>, ParseErrorReason>>();
+                    let (x, y, vel_x, vel_y, acc_x, acc_y) = match &values {
+                        Ok(values) => (
+                            values[0], values[1], values[2], values[3], values[4], values[5],
+                        ),
+                        Err(_) => (0., 0., 0., 0., 0., 0.),
+                    };
+
+                    spawn_player(
+                        &mut commands,
+                        &mut meshes,
+                        &mut materials,
+                        Vec2::new(x, y),
+                        Vec2::new(vel_x, vel_y),
+                        Vec2::new(acc_x, acc_y),
+                    );
+                    player_spawned = true;
+                    values.map(|_| ())
                 }
-                "slot" => {
-                    // let block_id = parts[1].parse::<usize>().unwrap();
-                    let count = parts[2].parse::<usize>().unwrap();
-
-                    match get_item_type_from_string_rep(parts[1], &parts[3..]) {
-                        Some(item_type) => {
-                            insert_block_to_inventory(&mut inventory, item_type, count);
-                        }
-                        None => {
-                            inventory.slots.push(None);
-                        }
+                "slot" => match get_slot_from_string_rep(&parts) {
+                    Ok(slot) => {
+                        inventory.slots.push(slot);
+                        Ok(())
                     }
-                }
-
-                _ => {}
+                    // keep the slot, so the ones after it don't move
+                    Err(reason) => {
+                        inventory.slots.push(None);
+                        Err(reason)
+                    }
+                },
+                "" => Ok(()),
+                _ => Err(ParseErrorReason::UnknownType(String::from(parts[0]))),
+            };
+            if let Err(reason) = result {
+                errors.push(ParseError {
+                    file: file.clone(),
+                    line: i + 1,
+                    reason,
+                });
             }
         }
+        load_errors.add_file_errors(&file, &players_string, errors);
+    }
+    let size = inventory.slots.len().max(INVENTORY_SIZE);
+    inventory.slots.resize(size, None);
+    // slots saved before there were stack limits can hold too many items, so the extra ones are
+    // moved to other slots
+    let mut overflow = Vec::new();
+    for slot in inventory.slots.iter_mut().flatten() {
+        if slot.count > slot.max_stack {
+            overflow.push((
+                slot.item_type,
+                slot.count - slot.max_stack,
+                slot.data.clone(),
+            ));
+            slot.count = slot.max_stack;
+        }
+    }
+    for (item_type, number, data) in overflow {
+        let lost = insert_block_to_inventory(&mut inventory, item_type, number, data);
+        if lost > 0 {
+            println!("there wasn't room in the inventory for {} items", lost);
+        }
+    }
+    if !player_spawned {
+        spawn_player(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            Vec2::ZERO,
+            Vec2::ZERO,
+            Vec2::ZERO,
+        );
+    }
+}
+
+// an inventory slot as its item's name, its count and then its data if it has any. empty slots are
+// written as "nothing 0"
+pub fn make_string_rep_of_slot(slot: &Option<InventorySlot>) -> String {
+    let Some(slot) = slot else {
+        return get_string_rep_from_item_type(None).0 + " 0";
+    };
+    let mut slot_string = format!(
+        "{} {}",
+        get_string_rep_from_item_type(Some(slot.item_type)).0,
+        slot.count
+    );
+    if let Some(data) = &slot.data {
+        slot_string.push(' ');
+        slot_string.push_str(data.as_str());
     }
+    slot_string
+}
+
+// reads an inventory slot from its string representation, or None if the slot is empty
+pub fn get_slot_from_string_rep(parts: &[&str]) -> Result<Option<InventorySlot>, ParseErrorReason> {
+    let count = parse_word::<usize>(parts, 2)?;
+    if parts[1] == "nothing" || count == 0 {
+        return Ok(None);
+    }
+    let item_type = get_item_type_from_string_rep(parts[1], &[])?;
+    // everything after the count is the item's data
+    let data = if parts.len() > 3 {
+        Some(parts[3..].join(" "))
+    } else {
+        None
+    };
+    Ok(Some(InventorySlot::new(item_type, count, data)))
 }
 
 pub fn despawn_players(
@@ -134,7 +255,6 @@ pub fn despawn_players(
     players: Query<Entity, With<Player>>,
     mut inventory: ResMut<PlayerInventory>,
 ) {
-    inventory.items.clear();
     inventory.slots.clear();
     inventory.selected_slot = 0;
     for player in &players {
@@ -144,15 +264,19 @@ pub fn despawn_players(
 
 // a system that checks the player position and loads chunks in a square of
 // side 2*RENDER_RADIUS + 1 centered around the player
+#[allow(clippy::too_many_arguments)]
 pub fn load_close_chunks(
     mut commands: Commands,
     mut chunks_to_save: ResMut<ChunksToSave>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    mut ground_map: ResMut<GroundMap>,
     mut block_map: ResMut<Map>,
     player: Query<&Transform, With<Player>>,
     asset_server: Res<AssetServer>,
     mut block_update_queue: ResMut<BlockUpdateQueue>,
+    mut load_errors: ResMut<LoadErrors>,
+    current_world: Res<CurrentWorld>,
 ) {
     let player_translation = player.get_single().unwrap().translation;
     let player_pos = (
@@ -167,10 +291,13 @@ pub fn load_close_chunks(
                 &mut chunks_to_save,
                 &mut meshes,
                 &mut materials,
+                &mut ground_map,
                 &mut block_map,
                 &asset_server,
                 (player_pos.0 + i, player_pos.1 + j),
                 &mut block_update_queue,
+                &mut load_errors,
+                &current_world,
             );
         }
     }
@@ -179,83 +306,312 @@ pub fn load_close_chunks(
 // loads a chunk. if already loaded, skips.
 // if the chunk was previously loaded and unloaded, the data will be presend in
 // the ChunksToSave resource, so it loads from there. Otherwise, if a chunk file
-// exists, it loads that. Else, it generates new trees and rocks for this chunk
+// exists, it loads that. Else, it generates new trees and rocks for this chunk.
+// if the chunk is still being simulated, its blocks never left the map, so only the
+// things in ChunksToSave are loaded
+#[allow(clippy::too_many_arguments)]
 pub fn load_chunk(
     commands: &mut Commands,
     chunks_to_save: &mut ResMut<ChunksToSave>,
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<ColorMaterial>>,
+    ground_map: &mut ResMut<GroundMap>,
     block_map: &mut ResMut<Map>,
     asset_server: &Res<AssetServer>,
     pos: (i32, i32),
     block_update_queue: &mut ResMut<BlockUpdateQueue>,
+    load_errors: &mut ResMut<LoadErrors>,
+    current_world: &CurrentWorld,
 ) {
     if chunks_to_save.chunks_loaded.contains(&pos) {
         return;
     }
     chunks_to_save.chunks_loaded.insert(pos);
-    commands.spawn((
-        SpriteBundle {
-            sprite: Sprite {
-                // color: Color::rgb(0.5, 0.7, 0.3),
-                color: Color::rgb(0.2, 0.3, 0.1),
-                custom_size: Some(Vec2::new(16., 16.)),
-                ..default()
-            },
-            transform: Transform::from_translation(Vec3::new(
-                (pos.0 * 16) as f32 + 8.,
-                (pos.1 * 16) as f32 + 8.,
-                -0.1,
-            )),
-            ..default()
-        },
-        ChunkBackground {},
-    ));
+    let was_simulated = chunks_to_save.chunks_simulated.remove(&pos);
+    let seed = current_world.seed;
+    let file = current_world.get_chunk_file(pos);
+    let (region, index) = get_region_of_chunk(pos);
+    let region_file = current_world.get_region_file(region);
+    // errors in a chunk that came from a region are reported against the region and the chunk's
+    // index in it, since the chunk file doesn't exist
+    let mut from_region = false;
+    let mut errors = Vec::new();
+    let mut text = String::new();
     let mut entities = String::new();
-    if let Some(text) = chunks_to_save.chunks.get(&pos) {
-        entities.push_str(&text);
+    if let Some(chunk_text) = chunks_to_save.chunks.get(&pos) {
+        text.push_str(chunk_text);
+        entities.push_str(chunk_text);
         chunks_to_save.chunks.remove(&pos);
-    } else if let Ok(text) = fs::read_to_string(format!("./assets/chunk_{}_{}.txt", pos.0, pos.1)) {
-        entities.push_str(&text);
+    } else if was_simulated {
+        let ground = generate_ground(seed, pos);
+        spawn_ground(commands, meshes, materials, ground_map, seed, pos, ground);
+        return;
+    } else if let Some((version, lines)) = read_chunk_from_region(&region_file, index, load_errors)
+    {
+        from_region = true;
+        text.push_str(&lines.join("\n"));
+        entities.push_str(&migrate_chunk_lines(version, lines).join("\n"));
+    } else if let Ok(file_text) = fs::read_to_string(&file) {
+        // chunks saved before there were region files
+        text.push_str(&file_text);
+        entities.push_str(&read_chunk_file(pos, &file, &file_text, &mut errors));
     } else {
         // generate the chunk
-        generate_trees(commands, meshes, materials, pos);
-        generate_rocks(commands, meshes, materials, asset_server, pos);
+        generate_trees(commands, meshes, materials, pos, current_world.seed);
+        generate_rocks(commands, meshes, materials, pos, current_world.seed);
+        generate_cacti(commands, meshes, materials, pos, current_world.seed);
+        generate_boulders(commands, meshes, materials, pos, current_world.seed);
+        generate_ores(commands, meshes, materials, pos, current_world.seed);
+        let ground = generate_ground(seed, pos);
+        spawn_ground(commands, meshes, materials, ground_map, seed, pos, ground);
         // println!("wtf");
         return;
     }
+    // chunks saved before there was ground get it generated
+    let mut ground = None;
+    // what's in the chests is put in them once all the blocks are spawned
+    let mut chests: HashMap<(i32, i32), ChestInventory> = HashMap::new();
+    // lines that can't be read are skipped, and the file gets backed up
+    let error_file = if from_region {
+        get_region_chunk_name(&region_file, index)
+    } else {
+        file.clone()
+    };
     let lines = entities.split('\n').collect::<Vec<&str>>();
-    for line in lines {
+    for (i, line) in lines.iter().enumerate() {
         let parts = line.split(' ').collect::<Vec<&str>>();
-        match parts[0] {
-            "tree" => {
-                spawn_tree_from_string_rep(commands, meshes, materials, parts);
+        let result = match parts[0] {
+            "tree" => spawn_tree_from_string_rep(commands, meshes, materials, parts),
+            "rock" => spawn_rock_from_string_rep(commands, meshes, materials, parts),
+            "cactus" => spawn_cactus_from_string_rep(commands, meshes, materials, parts),
+            "boulder" => spawn_boulder_from_string_rep(commands, meshes, materials, parts),
+            "copper_ore" | "iron_ore" | "redstone_ore" => {
+                spawn_ore_from_string_rep(commands, meshes, materials, parts)
             }
-            "rock" => {
-                spawn_rock_from_string_rep(commands, meshes, materials, parts);
+            "item" => spawn_dropped_item_from_string_rep(commands, asset_server, parts),
+            "chest_slot" => {
+                get_chest_slot_from_string_rep(&parts).map(|(chest_pos, index, slot)| {
+                    chests.entry(chest_pos).or_default().slots[index] = slot;
+                })
             }
+            "ground" => get_ground_from_string_rep(&parts).map(|tiles| ground = Some(tiles)),
             "block" => {
-                let x = parts[2].parse::<i32>().unwrap();
-                let y = parts[3].parse::<i32>().unwrap();
-                spawn_block(
-                    commands,
-                    block_map,
-                    (x, y),
-                    get_block_type_from_string_rep(parts[1], &parts[4..]).unwrap(),
-                    block_update_queue,
-                    &asset_server,
-                )
+                get_block_pos_and_type_from_string_rep(&parts).map(|(block_pos, block_type)| {
+                    spawn_block(
+                        commands,
+                        block_map,
+                        block_pos,
+                        block_type,
+                        block_update_queue,
+                        asset_server,
+                    )
+                })
+            }
+            "" => Ok(()),
+            _ => Err(ParseErrorReason::UnknownType(String::from(parts[0]))),
+        };
+        if let Err(reason) = result {
+            errors.push(ParseError {
+                file: error_file.clone(),
+                line: i + 1,
+                reason,
+            });
+        }
+    }
+    for (chest_pos, chest) in chests {
+        match block_map.blocks.get(&chest_pos) {
+            Some((entity, BlockType::Chest)) => {
+                commands.entity(*entity).insert(chest);
+            }
+            _ => println!(
+                "there's no chest at {}, {} for its items to go in",
+                chest_pos.0, chest_pos.1
+            ),
+        }
+    }
+    if from_region {
+        load_errors.add_region_chunk_errors(&region_file, index, &text, errors);
+    } else {
+        load_errors.add_file_errors(&file, &text, errors);
+    }
+    let ground = ground.unwrap_or_else(|| generate_ground(seed, pos));
+    spawn_ground(commands, meshes, materials, ground_map, seed, pos, ground);
+}
+
+// reads a chunk from its region file, as the chunk format version it was saved with and its lines.
+// None if it was never saved to a region, or the region is too damaged to read
+fn read_chunk_from_region(
+    file: &str,
+    index: usize,
+    load_errors: &mut ResMut<LoadErrors>,
+) -> Option<(u32, Vec<String>)> {
+    let result = read_region_chunk(file, index)
+        .and_then(|packed| packed.map(|packed| unpack_chunk(&packed)).transpose());
+    match result {
+        Ok(chunk) => chunk,
+        Err(error) => {
+            load_errors.add_damaged_file(file, error);
+            None
+        }
+    }
+}
+
+// reads a block's position and type from the words in its string representation
+fn get_block_pos_and_type_from_string_rep(
+    parts: &[&str],
+) -> Result<((i32, i32), BlockType), ParseErrorReason> {
+    let x = parse_word::<i32>(parts, 2)?;
+    let y = parse_word::<i32>(parts, 3)?;
+    let block_type = get_block_type_from_string_rep(parts[1], &parts[4..])?;
+    Ok(((x, y), block_type))
+}
+
+// the first line of a chunk file. has the format version, the chunk's coordinates and how many of
+// each kind of thing are saved in it
+pub fn make_chunk_file_header(pos: (i32, i32), entities: &str) -> String {
+    format!(
+        "chunk_format {} {} {} {}\n",
+        CHUNK_FORMAT_VERSION,
+        pos.0,
+        pos.1,
+        get_chunk_entity_counts(entities, CHUNK_FORMAT_VERSION)
+    )
+}
+
+// the number of each kind of thing in a chunk that a header of the given format version counts,
+// separated by spaces like in the header
+fn get_chunk_entity_counts(entities: &str, version: u32) -> String {
+    let count = |kind: &str| {
+        entities
+            .lines()
+            .filter(|line| line.split(' ').next() == Some(kind))
+            .count()
+    };
+    COUNTED_LINE_KINDS
+        .iter()
+        .filter(|(_, added_in)| *added_in <= version)
+        .map(|(kind, _)| count(kind).to_string())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+// reads the header of a chunk file and returns the rest of it, upgraded to the current format
+// version. files from before the header was added count as version 0. the header is blanked out
+// instead of removed, so the line numbers in any errors still match the file
+fn read_chunk_file(
+    pos: (i32, i32),
+    file: &str,
+    text: &str,
+    errors: &mut Vec<ParseError>,
+) -> String {
+    let mut lines = text.split('\n').map(String::from).collect::<Vec<String>>();
+    let mut version = 0;
+    if lines[0].starts_with("chunk_format ") {
+        let header = std::mem::take(&mut lines[0]);
+        let parts = header.split(' ').collect::<Vec<&str>>();
+        let header_pos;
+        match get_chunk_file_header_from_string_rep(&parts) {
+            Ok((header_version, pos)) => {
+                version = header_version;
+                header_pos = pos;
             }
-            _ => {}
+            // without a header, the best guess is that it was saved by this version
+            Err(reason) => {
+                errors.push(ParseError {
+                    file: String::from(file),
+                    line: 1,
+                    reason,
+                });
+                return migrate_chunk_lines(CHUNK_FORMAT_VERSION, lines).join("\n");
+            }
+        }
+        // the file is still loaded, but it's reported and backed up like any other damaged data
+        if header_pos != pos {
+            errors.push(ParseError {
+                file: String::from(file),
+                line: 1,
+                reason: ParseErrorReason::HeaderMismatch(format!(
+                    "it says it's chunk {} {}",
+                    header_pos.0, header_pos.1
+                )),
+            });
         }
+        let counts = get_chunk_entity_counts(&lines.join("\n"), version);
+        let header_counts = parts[4..].join(" ");
+        if counts != header_counts {
+            errors.push(ParseError {
+                file: String::from(file),
+                line: 1,
+                reason: ParseErrorReason::HeaderMismatch(format!(
+                    "it counts {} but there are {}",
+                    header_counts, counts
+                )),
+            });
+        }
+    }
+    if version > CHUNK_FORMAT_VERSION {
+        println!(
+            "chunk {} {} was saved by a newer version of the game (format {}), loading it anyway",
+            pos.0, pos.1, version
+        );
+    }
+    migrate_chunk_lines(version, lines).join("\n")
+}
+
+// reads the format version and chunk coordinates from the words in a chunk file's header
+fn get_chunk_file_header_from_string_rep(
+    parts: &[&str],
+) -> Result<(u32, (i32, i32)), ParseErrorReason> {
+    let version = parse_word::<u32>(parts, 1)?;
+    let x = parse_word::<i32>(parts, 2)?;
+    let y = parse_word::<i32>(parts, 3)?;
+    Ok((version, (x, y)))
+}
+
+// upgrades the lines of a chunk file from an older format version to the current one, a version
+// at a time
+pub fn migrate_chunk_lines(mut version: u32, mut lines: Vec<String>) -> Vec<String> {
+    while version < CHUNK_FORMAT_VERSION {
+        lines = match version {
+            // repeaters and inverters didn't have a delay yet, so give them the default one
+            0 => lines
+                .into_iter()
+                .map(|line| {
+                    let parts = line.split_whitespace().collect::<Vec<&str>>();
+                    if parts.len() == 6
+                        && parts[0] == "block"
+                        && (parts[1] == "repeater" || parts[1] == "inverter")
+                    {
+                        format!("{} {}", parts.join(" "), DEFAULT_PROCESSOR_DELAY)
+                    } else {
+                        line
+                    }
+                })
+                .collect(),
+            // cacti and boulders were added
+            1 => lines,
+            // chunks started saving their ground
+            2 => lines,
+            // ore deposits were added
+            3 => lines,
+            // items dropped on the ground started being saved with their chunk
+            4 => lines,
+            // chests started saving what's in them
+            5 => lines,
+            _ => lines,
+        };
+        version += 1;
     }
+    lines
 }
 
-// only unloads the background square of a far chunks.
+// only unloads the background square of a far chunks, and the ground drawn on it. the ground
+// will be added to ChunksToSave
 pub fn unload_far_chunk_backgrounds(
     mut commands: Commands,
     backgrounds: Query<(&Transform, Entity), With<ChunkBackground>>,
     mut chunks_to_save: ResMut<ChunksToSave>,
+    mut ground_map: ResMut<GroundMap>,
     player: Query<&Transform, With<Player>>,
 ) {
     let player_translation = player.get_single().unwrap().translation;
@@ -275,10 +631,34 @@ pub fn unload_far_chunk_backgrounds(
             continue;
         }
         commands.entity(entity).despawn();
+        // the chunk gets an entry even without any ground, so the things in it that are unloaded
+        // after it stops being loaded still have somewhere to go
+        let chunk_str = chunks_to_save.chunks.entry(pos).or_default();
+        if let Some(tiles) = ground_map.chunks.remove(&pos) {
+            chunk_str.push_str(make_string_rep_of_ground(&tiles).as_str());
+        }
         chunks_to_save.chunks_loaded.remove(&pos);
     }
 }
 
+// the ground of every loaded chunk will be added to ChunksToSave. the backgrounds it's drawn on
+// are despawned after saving, by unload_all_chunk_backgrounds_and_clear_chunks_to_save
+pub fn unload_all_ground(
+    mut ground_map: ResMut<GroundMap>,
+    mut chunks_to_save: ResMut<ChunksToSave>,
+) {
+    let ChunksToSave {
+        chunks,
+        chunks_loaded,
+        ..
+    } = chunks_to_save.as_mut();
+    for (pos, tiles) in ground_map.chunks.drain() {
+        if let Some(chunk_str) = get_chunk_text_to_save(chunks, chunks_loaded, pos) {
+            chunk_str.push_str(make_string_rep_of_ground(&tiles).as_str());
+        }
+    }
+}
+
 // clears only chunks_loaded, so data is not lost, but can cause inconsistancy of some entites are still loaded
 pub fn unload_all_chunk_backgrounds_and_clear_chunks_to_save(
     mut commands: Commands,
@@ -289,30 +669,313 @@ pub fn unload_all_chunk_backgrounds_and_clear_chunks_to_save(
         commands.entity(entity).despawn();
     }
     chunks_to_save.chunks_loaded.clear();
+    chunks_to_save.chunks_simulated.clear();
 }
 
-// saves the chunks in ChunksToSave to their respective files and clears it. Doesn't affect loaded chunks
-pub fn save_chunks_to_file(mut chunks_to_save: ResMut<ChunksToSave>) {
-    for (pos, entities) in chunks_to_save.chunks.iter() {
-        if let Ok(_) = fs::write(format!("./assets/chunk_{}_{}.txt", pos.0, pos.1), entities) {
-            println!("saved chunk {} {} successfully", pos.0, pos.1);
-        } else {
-            println!("error saving chunks");
+// saves which chunks have chunk loaders in them, so they can start being simulated again as soon
+// as the game is started
+pub fn save_chunk_loaders(block_map: Res<Map>, current_world: Res<CurrentWorld>) {
+    let chunks = block_map
+        .chunk_loaders
+        .iter()
+        .map(|block_pos| get_chunk_of_block(*block_pos))
+        .collect::<HashSet<(i32, i32)>>();
+    let mut chunks_string = String::new();
+    for chunk in chunks {
+        chunks_string.push_str(format!("{} {}\n", chunk.0, chunk.1).as_str());
+    }
+    if let Err(error) =
+        write_file_atomically(&current_world.get_chunk_loaders_file(), chunks_string)
+    {
+        println!("error saving chunk loaders: {}", error);
+    }
+}
+
+// loads the chunks around every saved chunk loader. the ones that are far from the player get
+// hidden by unload_far_blocks, but keep being simulated
+#[allow(clippy::too_many_arguments)]
+pub fn load_chunk_loader_chunks(
+    mut commands: Commands,
+    mut chunks_to_save: ResMut<ChunksToSave>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut ground_map: ResMut<GroundMap>,
+    mut block_map: ResMut<Map>,
+    asset_server: Res<AssetServer>,
+    mut block_update_queue: ResMut<BlockUpdateQueue>,
+    mut load_errors: ResMut<LoadErrors>,
+    current_world: Res<CurrentWorld>,
+) {
+    let file = current_world.get_chunk_loaders_file();
+    let Ok(chunks_string) = fs::read_to_string(&file) else {
+        return;
+    };
+    let mut errors = Vec::new();
+    for (line, text) in chunks_string.lines().enumerate() {
+        let parts = text.split(' ').collect::<Vec<&str>>();
+        let chunk = match (parse_word::<i32>(&parts, 0), parse_word::<i32>(&parts, 1)) {
+            (Ok(x), Ok(y)) => (x, y),
+            (Err(reason), _) | (_, Err(reason)) => {
+                errors.push(ParseError {
+                    file: file.clone(),
+                    line: line + 1,
+                    reason,
+                });
+                continue;
+            }
+        };
+        for i in -CHUNK_LOADER_RADIUS..(CHUNK_LOADER_RADIUS + 1) {
+            for j in -CHUNK_LOADER_RADIUS..(CHUNK_LOADER_RADIUS + 1) {
+                load_chunk(
+                    &mut commands,
+                    &mut chunks_to_save,
+                    &mut meshes,
+                    &mut materials,
+                    &mut ground_map,
+                    &mut block_map,
+                    &asset_server,
+                    (chunk.0 + i, chunk.1 + j),
+                    &mut block_update_queue,
+                    &mut load_errors,
+                    &current_world,
+                );
+            }
         }
     }
+    load_errors.add_file_errors(&file, &chunks_string, errors);
+}
+
+// saves the chunks in ChunksToSave to their respective files and clears it. Doesn't affect loaded chunks
+pub fn save_chunks_to_file(
+    mut chunks_to_save: ResMut<ChunksToSave>,
+    current_world: Res<CurrentWorld>,
+) {
+    if write_chunks_to_regions(&chunks_to_save.chunks, &current_world) {
+        println!("saved {} chunks successfully", chunks_to_save.chunks.len());
+    } else {
+        println!("error saving chunks");
+    }
     chunks_to_save.chunks.clear();
 }
 
-// the unloaded blocks will be added to ChunksToSave
+// whether it's time to autosave. the world's autosave_seconds is checked every frame, so changing it
+// takes effect straight away. outside of a world the time is kept at zero, so the first autosave in
+// a world is a whole interval after entering it
+pub fn autosave_is_due(
+    time: Res<Time>,
+    current_world: Res<CurrentWorld>,
+    app_state: Res<State<AppState>>,
+    mut since_last_autosave: Local<f32>,
+) -> bool {
+    if *app_state.get() != AppState::Game {
+        *since_last_autosave = 0.;
+        return false;
+    }
+    *since_last_autosave += time.delta_seconds();
+    if *since_last_autosave < current_world.autosave_seconds {
+        return false;
+    }
+    *since_last_autosave = 0.;
+    true
+}
+
+// saves a copy of every chunk, both the ones in ChunksToSave and the loaded ones, without unloading
+// anything. so if the game crashes, at most the world's autosave_seconds of progress is lost
+#[allow(clippy::too_many_arguments)]
+pub fn autosave_chunks(
+    blocks: Query<(Entity, &Transform, Option<&ChestInventory>), With<BlockEntity>>,
+    block_map: Res<Map>,
+    rocks: Query<&Collectible, With<Rock>>,
+    trees: Query<&Collectible, With<Tree>>,
+    cacti: Query<&Collectible, With<Cactus>>,
+    boulders: Query<&Collectible, With<Boulder>>,
+    ores: Query<(&Collectible, &Ore)>,
+    dropped_items: Query<&DroppedItem>,
+    ground_map: Res<GroundMap>,
+    chunks_to_save: Res<ChunksToSave>,
+    current_world: Res<CurrentWorld>,
+    inventory_screen: Res<InventoryScreen>,
+) {
+    let chunks_loaded = &chunks_to_save.chunks_loaded;
+    let mut chunks = chunks_to_save.chunks.clone();
+    // loaded chunks are saved even if they're empty now, so everything gathered from them stays gone
+    for pos in chunks_loaded.iter() {
+        chunks.entry(*pos).or_default();
+    }
+    for (pos, tiles) in ground_map.chunks.iter() {
+        if let Some(chunk) = get_chunk_text_to_save(&mut chunks, chunks_loaded, *pos) {
+            chunk.push_str(make_string_rep_of_ground(tiles).as_str());
+        }
+    }
+    for (entity, block_transform, chest) in &blocks {
+        let block_pos = (
+            block_transform.translation.x.round() as i32,
+            block_transform.translation.y.round() as i32,
+        );
+        let Some((_, block_type)) = block_map.blocks.get(&block_pos) else {
+            continue;
+        };
+        let string_rep = get_string_rep_from_block_type(Some(*block_type));
+        let chunk_pos = get_chunk_of_block(block_pos);
+        // things in chunks that aren't loaded are still saved in their regions as they were
+        let Some(chunk) = get_chunk_text_to_save(&mut chunks, chunks_loaded, chunk_pos) else {
+            continue;
+        };
+        chunk.push_str(get_block_rep_from_string_rep_and_pos(string_rep, block_pos).as_str());
+        if let Some(chest) = chest {
+            let chest = get_chest_to_save(entity, chest, &inventory_screen);
+            chunk.push_str(make_string_rep_of_chest_contents(block_pos, &chest).as_str());
+        }
+    }
+    let collectibles = rocks
+        .iter()
+        .map(|rock| (rock, make_string_rep_of_rock(rock)))
+        .chain(
+            trees
+                .iter()
+                .map(|tree| (tree, make_string_rep_of_tree(tree))),
+        )
+        .chain(
+            cacti
+                .iter()
+                .map(|cactus| (cactus, make_string_rep_of_cactus(cactus))),
+        )
+        .chain(
+            boulders
+                .iter()
+                .map(|boulder| (boulder, make_string_rep_of_boulder(boulder))),
+        )
+        .chain(
+            ores.iter()
+                .map(|(collectible, ore)| (collectible, make_string_rep_of_ore(collectible, ore))),
+        );
+    for (collectible, rep_string) in collectibles {
+        let pos = (
+            (collectible.pos.x / 16.).floor() as i32,
+            (collectible.pos.y / 16.).floor() as i32,
+        );
+        let Some(chunk) = get_chunk_text_to_save(&mut chunks, chunks_loaded, pos) else {
+            continue;
+        };
+        chunk.push_str(rep_string.as_str());
+    }
+    for dropped_item in &dropped_items {
+        let pos = (
+            (dropped_item.pos.x / 16.).floor() as i32,
+            (dropped_item.pos.y / 16.).floor() as i32,
+        );
+        let Some(chunk) = get_chunk_text_to_save(&mut chunks, chunks_loaded, pos) else {
+            continue;
+        };
+        chunk.push_str(make_string_rep_of_dropped_item(dropped_item).as_str());
+    }
+
+    if write_chunks_to_regions(&chunks, &current_world) {
+        println!("autosaved {} chunks", chunks.len());
+    } else {
+        println!("error autosaving chunks");
+    }
+}
+
+// packs chunks into their region files, keeping the other chunks that are already in them. returns
+// whether every region was saved
+fn write_chunks_to_regions(
+    chunks: &HashMap<(i32, i32), String>,
+    current_world: &CurrentWorld,
+) -> bool {
+    let mut regions: HashMap<(i32, i32), Vec<(usize, &String)>> = HashMap::new();
+    for (pos, entities) in chunks.iter() {
+        let (region, index) = get_region_of_chunk(*pos);
+        regions.entry(region).or_default().push((index, entities));
+    }
+    if fs::create_dir_all(get_regions_dir(&current_world.name)).is_err() {
+        return false;
+    }
+    let mut saved = true;
+    for (region, chunks) in regions {
+        let file = current_world.get_region_file(region);
+        // the chunks that aren't being saved are kept as they are. if the region can't be read,
+        // it's backed up and replaced with just the chunks being saved
+        let mut packed_chunks = read_region(&file).unwrap_or_else(|error| {
+            println!("region file {} is damaged ({})", file, error);
+            backup_damaged_file(&file);
+            HashMap::new()
+        });
+        for (index, entities) in chunks {
+            packed_chunks.insert(index, pack_chunk(entities));
+        }
+        if let Err(error) = write_region(&file, &packed_chunks) {
+            println!("error saving region {} {}: {}", region.0, region.1, error);
+            saved = false;
+        }
+    }
+    saved
+}
+
+pub fn get_chunk_of_block(block_pos: (i32, i32)) -> (i32, i32) {
+    (block_pos.0.div_euclid(16), block_pos.1.div_euclid(16))
+}
+
+// the text in ChunksToSave that something being unloaded or saved in the chunk at pos is added to.
+// a chunk only gets a new entry if it's loaded. one that isn't loaded and has no entry is still all
+// in its region, and an entry with only some of its things would replace the whole chunk there
+fn get_chunk_text_to_save<'a>(
+    chunks: &'a mut HashMap<(i32, i32), String>,
+    chunks_loaded: &HashSet<(i32, i32)>,
+    pos: (i32, i32),
+) -> Option<&'a mut String> {
+    if !chunks_loaded.contains(&pos) && !chunks.contains_key(&pos) {
+        return None;
+    }
+    Some(chunks.entry(pos).or_default())
+}
+
+// the chunks that have a ticket, so they keep being simulated while the player is far away.
+// chunk loaders give the chunks around them a ticket, and so do pending block updates if the
+// world's keep_chunks_with_pending_updates setting is on
+fn get_chunk_tickets(
+    block_map: &Map,
+    block_update_queue: &UpdateQueue,
+    keep_chunks_with_pending_updates: bool,
+) -> HashSet<(i32, i32)> {
+    let mut tickets = HashSet::new();
+    for block_pos in block_map.chunk_loaders.iter() {
+        let chunk = get_chunk_of_block(*block_pos);
+        for i in -CHUNK_LOADER_RADIUS..(CHUNK_LOADER_RADIUS + 1) {
+            for j in -CHUNK_LOADER_RADIUS..(CHUNK_LOADER_RADIUS + 1) {
+                tickets.insert((chunk.0 + i, chunk.1 + j));
+            }
+        }
+    }
+    if keep_chunks_with_pending_updates {
+        for block_pos in block_update_queue.pending_positions() {
+            tickets.insert(get_chunk_of_block(*block_pos));
+        }
+    }
+    tickets
+}
+
+// the unloaded blocks will be added to ChunksToSave. blocks in far chunks that have a ticket
+// are hidden instead, and stay in the map so they keep being simulated
 pub fn unload_far_blocks(
     mut commands: Commands,
-    blocks: Query<(&Transform, Entity), With<BlockEntity>>,
+    mut blocks: Query<
+        (&Transform, Entity, &mut Visibility, Option<&ChestInventory>),
+        With<BlockEntity>,
+    >,
     mut block_map: ResMut<Map>,
     mut chunks_to_save: ResMut<ChunksToSave>,
     player: Query<&Transform, With<Player>>,
+    block_update_queue: Res<BlockUpdateQueue>,
+    current_world: Res<CurrentWorld>,
 ) {
     let player = player.get_single().unwrap().translation;
-    for (block_transform, entity) in &blocks {
+    let tickets = get_chunk_tickets(
+        &block_map,
+        &block_update_queue,
+        current_world.keep_chunks_with_pending_updates,
+    );
+    for (block_transform, entity, mut visibility, chest) in &mut blocks {
         let block_pos = (
             block_transform.translation.x as i32,
             block_transform.translation.y as i32,
@@ -324,31 +987,49 @@ pub fn unload_far_blocks(
         if (pos.0 - (player.x / 16.).floor() as i32).abs() <= RENDER_RADIUS
             && (pos.1 - (player.y / 16.).floor() as i32).abs() <= RENDER_RADIUS
         {
+            *visibility = Visibility::Inherited;
             continue;
         }
+        if tickets.contains(&pos) {
+            *visibility = Visibility::Hidden;
+            chunks_to_save.chunks_simulated.insert(pos);
+            continue;
+        }
+        chunks_to_save.chunks_simulated.remove(&pos);
+        let ChunksToSave {
+            chunks,
+            chunks_loaded,
+            ..
+        } = chunks_to_save.as_mut();
+        // the block stays until its chunk can be saved, instead of wiping the rest of the chunk
+        let Some(block_str) = get_chunk_text_to_save(chunks, chunks_loaded, pos) else {
+            continue;
+        };
         let string_rep =
             get_string_rep_from_block_type(Some(block_map.blocks.get(&block_pos).unwrap().1));
-        let block_string = get_block_rep_from_string_rep_and_pos(string_rep, block_pos);
-
-        if let Some(block_str) = chunks_to_save.chunks.get_mut(&pos) {
-            block_str.push_str(block_string.as_str());
-        } else {
-            chunks_to_save.chunks.insert(pos, block_string);
+        block_str.push_str(get_block_rep_from_string_rep_and_pos(string_rep, block_pos).as_str());
+        if let Some(chest) = chest {
+            block_str.push_str(make_string_rep_of_chest_contents(block_pos, chest).as_str());
         }
 
         commands.entity(entity).despawn();
         block_map.blocks.remove(&block_pos);
+        block_map.pressure_plates.remove(&block_pos);
+        block_map.chunk_loaders.remove(&block_pos);
+        block_map.inserters.remove(&block_pos);
     }
 }
 
-// the unloaded blocks will be added to ChunksToSave
+// the unloaded blocks will be added to ChunksToSave. their pending block updates are dropped, so
+// they don't happen in the next world that's loaded
 pub fn unload_all_blocks(
     mut commands: Commands,
-    blocks: Query<(&Transform, Entity), With<BlockEntity>>,
+    blocks: Query<(&Transform, Entity, Option<&ChestInventory>), With<BlockEntity>>,
     mut block_map: ResMut<Map>,
     mut chunks_to_save: ResMut<ChunksToSave>,
+    mut block_update_queue: ResMut<BlockUpdateQueue>,
 ) {
-    for (block_transform, entity) in &blocks {
+    for (block_transform, entity, chest) in &blocks {
         let block_pos = (
             block_transform.translation.x.round() as i32,
             block_transform.translation.y.round() as i32,
@@ -357,25 +1038,43 @@ pub fn unload_all_blocks(
             (block_transform.translation.x / 16.).floor() as i32,
             (block_transform.translation.y / 16.).floor() as i32,
         );
+        commands.entity(entity).despawn();
+        let ChunksToSave {
+            chunks,
+            chunks_loaded,
+            ..
+        } = chunks_to_save.as_mut();
+        let Some(block_str) = get_chunk_text_to_save(chunks, chunks_loaded, pos) else {
+            println!(
+                "lost a block in chunk {} {}, which isn't loaded",
+                pos.0, pos.1
+            );
+            continue;
+        };
         let string_rep =
             get_string_rep_from_block_type(Some(block_map.blocks.get(&block_pos).unwrap().1));
-        let block_string = get_block_rep_from_string_rep_and_pos(string_rep, block_pos);
-        if let Some(block_str) = chunks_to_save.chunks.get_mut(&pos) {
-            block_str.push_str(block_string.as_str());
-        } else {
-            chunks_to_save.chunks.insert(pos, block_string);
+        block_str.push_str(get_block_rep_from_string_rep_and_pos(string_rep, block_pos).as_str());
+        if let Some(chest) = chest {
+            block_str.push_str(make_string_rep_of_chest_contents(block_pos, chest).as_str());
         }
-
-        commands.entity(entity).despawn();
     }
     block_map.blocks.clear();
+    block_map.pressure_plates.clear();
+    block_map.chunk_loaders.clear();
+    block_map.inserters.clear();
+    block_update_queue.0 = UpdateQueue::default();
 }
 
 // the unloaded collectibles will be added to ChunksToSave
+#[allow(clippy::too_many_arguments)]
 pub fn unload_far_collectibles(
     mut commands: Commands,
     rocks: Query<(&Collectible, Entity), With<Rock>>,
     trees: Query<(&Collectible, Entity), With<Tree>>,
+    cacti: Query<(&Collectible, Entity), With<Cactus>>,
+    boulders: Query<(&Collectible, Entity), With<Boulder>>,
+    ores: Query<(&Collectible, &Ore, Entity)>,
+    dropped_items: Query<(&DroppedItem, Entity)>,
     mut chunks_to_save: ResMut<ChunksToSave>,
     player: Query<&Transform, With<Player>>,
 ) {
@@ -386,10 +1085,10 @@ pub fn unload_far_collectibles(
     );
     let mut pos: (i32, i32) = (0, 0);
 
-    let mut do_stuff = |collectible: &Collectible, entity: Entity, rep_string: String| {
+    let mut do_stuff = |thing_pos: Vec2, entity: Entity, rep_string: String| {
         pos = (
-            (collectible.pos.x / 16.).floor() as i32,
-            (collectible.pos.y / 16.).floor() as i32,
+            (thing_pos.x / 16.).floor() as i32,
+            (thing_pos.y / 16.).floor() as i32,
         );
 
         if (pos.0 - player_pos.0).abs() <= RENDER_RADIUS
@@ -397,53 +1096,379 @@ pub fn unload_far_collectibles(
         {
             return;
         }
-        if let Some(chunk_str) = chunks_to_save.chunks.get_mut(&pos) {
-            chunk_str.push_str(rep_string.as_str());
-        } else {
-            chunks_to_save.chunks.insert(pos, rep_string);
-        }
+        // it stays until its chunk can be saved, instead of wiping the rest of the chunk
+        let ChunksToSave {
+            chunks,
+            chunks_loaded,
+            ..
+        } = &mut *chunks_to_save;
+        let Some(chunk_str) = get_chunk_text_to_save(chunks, chunks_loaded, pos) else {
+            return;
+        };
+        chunk_str.push_str(rep_string.as_str());
 
         commands.entity(entity).despawn();
     };
 
     for (collectible, entity) in &rocks {
         let rocks_string = make_string_rep_of_rock(collectible);
-        do_stuff(collectible, entity, rocks_string);
+        do_stuff(collectible.pos, entity, rocks_string);
     }
 
     for (collectible, entity) in &trees {
         let trees_string = make_string_rep_of_tree(collectible);
-        do_stuff(collectible, entity, trees_string);
+        do_stuff(collectible.pos, entity, trees_string);
+    }
+
+    for (collectible, entity) in &cacti {
+        let cacti_string = make_string_rep_of_cactus(collectible);
+        do_stuff(collectible.pos, entity, cacti_string);
+    }
+
+    for (collectible, entity) in &boulders {
+        let boulders_string = make_string_rep_of_boulder(collectible);
+        do_stuff(collectible.pos, entity, boulders_string);
+    }
+
+    for (collectible, ore, entity) in &ores {
+        let ores_string = make_string_rep_of_ore(collectible, ore);
+        do_stuff(collectible.pos, entity, ores_string);
+    }
+
+    for (dropped_item, entity) in &dropped_items {
+        let dropped_item_string = make_string_rep_of_dropped_item(dropped_item);
+        do_stuff(dropped_item.pos, entity, dropped_item_string);
     }
 }
 
 // the unloaded collectibles will be added to ChunksToSave
+#[allow(clippy::too_many_arguments)]
 pub fn unload_all_collectibles(
     rocks: Query<(&Collectible, Entity), With<Rock>>,
     trees: Query<(&Collectible, Entity), With<Tree>>,
+    cacti: Query<(&Collectible, Entity), With<Cactus>>,
+    boulders: Query<(&Collectible, Entity), With<Boulder>>,
+    ores: Query<(&Collectible, &Ore, Entity)>,
+    dropped_items: Query<(&DroppedItem, Entity)>,
     mut chunks_to_save: ResMut<ChunksToSave>,
     mut commands: Commands,
 ) {
-    let mut do_stuff = |collectible: &Collectible, entity: Entity, rep_string: String| {
+    let mut do_stuff = |thing_pos: Vec2, entity: Entity, rep_string: String| {
         let pos = (
-            (collectible.pos.x / 16.).floor() as i32,
-            (collectible.pos.y / 16.).floor() as i32,
+            (thing_pos.x / 16.).floor() as i32,
+            (thing_pos.y / 16.).floor() as i32,
         );
 
-        if let Some(chunk_str) = chunks_to_save.chunks.get_mut(&pos) {
-            chunk_str.push_str(rep_string.as_str());
-        } else {
-            chunks_to_save.chunks.insert(pos, rep_string);
-        }
-
         commands.entity(entity).despawn();
+        let ChunksToSave {
+            chunks,
+            chunks_loaded,
+            ..
+        } = &mut *chunks_to_save;
+        match get_chunk_text_to_save(chunks, chunks_loaded, pos) {
+            Some(chunk_str) => chunk_str.push_str(rep_string.as_str()),
+            None => println!(
+                "lost something in chunk {} {}, which isn't loaded",
+                pos.0, pos.1
+            ),
+        }
     };
     for (collectible, entity) in &rocks {
         let rocks_string = make_string_rep_of_rock(collectible);
-        do_stuff(collectible, entity, rocks_string);
+        do_stuff(collectible.pos, entity, rocks_string);
     }
     for (collectible, entity) in &trees {
         let trees_string = make_string_rep_of_tree(collectible);
-        do_stuff(collectible, entity, trees_string);
+        do_stuff(collectible.pos, entity, trees_string);
+    }
+    for (collectible, entity) in &cacti {
+        let cacti_string = make_string_rep_of_cactus(collectible);
+        do_stuff(collectible.pos, entity, cacti_string);
+    }
+    for (collectible, entity) in &boulders {
+        let boulders_string = make_string_rep_of_boulder(collectible);
+        do_stuff(collectible.pos, entity, boulders_string);
+    }
+    for (collectible, ore, entity) in &ores {
+        let ores_string = make_string_rep_of_ore(collectible, ore);
+        do_stuff(collectible.pos, entity, ores_string);
+    }
+    for (dropped_item, entity) in &dropped_items {
+        let dropped_item_string = make_string_rep_of_dropped_item(dropped_item);
+        do_stuff(dropped_item.pos, entity, dropped_item_string);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{get_world_dir, ItemType, WorldMetadata};
+    use bevy::ecs::system::RunSystemOnce;
+    use std::time::Duration;
+
+    fn make_map() -> Map {
+        Map {
+            blocks: HashMap::new(),
+            pressure_plates: HashSet::new(),
+            chunk_loaders: HashSet::new(),
+            inserters: HashSet::new(),
+        }
+    }
+
+    #[test]
+    fn bad_block_lines_are_errors() {
+        let cases = [
+            ("block wire 1", ParseErrorReason::TooShort),
+            (
+                "block wire x 2 0",
+                ParseErrorReason::BadWord(String::from("x")),
+            ),
+            (
+                "block not_a_block 1 2",
+                ParseErrorReason::UnknownType(String::from("not_a_block")),
+            ),
+        ];
+        for (line, reason) in cases {
+            let parts = line.split(' ').collect::<Vec<&str>>();
+            assert_eq!(
+                get_block_pos_and_type_from_string_rep(&parts).err(),
+                Some(reason),
+                "{}",
+                line
+            );
+        }
+        let parts = ["block", "wire", "1", "2", "0"];
+        assert_eq!(
+            get_block_pos_and_type_from_string_rep(&parts),
+            Ok(((1, 2), BlockType::Wire(0)))
+        );
+    }
+
+    // a slot that can't be read is skipped, but keeps its place, and the lines after it are still
+    // loaded. the players file is backed up, since saving would lose the bad line
+    #[test]
+    fn damaged_players_line_is_skipped_and_backed_up() {
+        let current_world = CurrentWorld(WorldMetadata {
+            name: String::from("damaged_players_line"),
+            ..default()
+        });
+        let file = current_world.get_players_file();
+        fs::create_dir_all(get_world_dir(&current_world.name)).unwrap();
+        let text = "slot wood 5\nslot wood five\nslot stone 3\npos 1 2 0 0 0 0\n";
+        fs::write(&file, text).unwrap();
+
+        let mut world = World::new();
+        world.insert_resource(Assets::<Mesh>::default());
+        world.insert_resource(Assets::<ColorMaterial>::default());
+        world.insert_resource(PlayerInventory {
+            selected_slot: 0,
+            slots: vec![],
+            held_slot: None,
+        });
+        world.insert_resource(LoadErrors::default());
+        world.insert_resource(current_world);
+        world.run_system_once(spawn_players);
+
+        let inventory = world.resource::<PlayerInventory>();
+        assert_eq!(
+            inventory.slots[..3],
+            [
+                Some(InventorySlot::new(ItemType::Wood, 5, None)),
+                None,
+                Some(InventorySlot::new(ItemType::Stone(0), 3, None)),
+            ]
+        );
+        let errors = &world.resource::<LoadErrors>().errors;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, 2);
+        assert_eq!(
+            errors[0].reason,
+            ParseErrorReason::BadWord(String::from("five"))
+        );
+        let player = world
+            .query_filtered::<&Transform, With<Player>>()
+            .single(&world);
+        assert_eq!(player.translation.truncate(), Vec2::new(1., 2.));
+        assert_eq!(
+            fs::read_to_string(format!("{}.corrupt", file)).unwrap(),
+            text
+        );
+        fs::remove_dir_all(get_world_dir("damaged_players_line")).unwrap();
+    }
+
+    // repeaters and inverters saved before they had a delay get the default one, and nothing else
+    // in the chunk changes on the way up to the current version
+    #[test]
+    fn version_0_chunk_is_migrated_to_the_current_format() {
+        let lines = [
+            "block repeater 1 2 0 3",
+            "block inverter -4 5 1 0",
+            "block wire 0 0 0",
+            "tree 1.5 2.5 3",
+        ]
+        .map(String::from)
+        .to_vec();
+        let migrated = migrate_chunk_lines(0, lines.clone());
+        assert_eq!(
+            migrated,
+            [
+                format!("block repeater 1 2 0 3 {}", DEFAULT_PROCESSOR_DELAY),
+                format!("block inverter -4 5 1 0 {}", DEFAULT_PROCESSOR_DELAY),
+                String::from("block wire 0 0 0"),
+                String::from("tree 1.5 2.5 3"),
+            ]
+        );
+        assert_eq!(
+            migrate_chunk_lines(CHUNK_FORMAT_VERSION, migrated.clone()),
+            migrated
+        );
+
+        // a file without a header is read as version 0
+        let mut errors = Vec::new();
+        let read = read_chunk_file((0, 0), "chunk_0_0.txt", &lines.join("\n"), &mut errors);
+        assert!(errors.is_empty());
+        assert_eq!(read, migrated.join("\n"));
+    }
+
+    // a header is read back as the current version, and blanked out so the lines keep their numbers
+    #[test]
+    fn chunk_file_header_is_read_back() {
+        let entities = "block wire 1 2 0\ntree 1.5 2.5 3\nchest_slot 3 4 0 wood 5\n";
+        let text = format!("{}{}", make_chunk_file_header((3, -2), entities), entities);
+        assert!(text.starts_with(&format!("chunk_format {} 3 -2 ", CHUNK_FORMAT_VERSION)));
+
+        let mut errors = Vec::new();
+        let read = read_chunk_file((3, -2), "chunk_3_-2.txt", &text, &mut errors);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(read, format!("\n{}", entities));
+    }
+
+    // a header for another chunk, or one that counts things that aren't there, is reported on its
+    // line, but the rest of the file is still read
+    #[test]
+    fn chunk_file_header_that_doesnt_match_is_an_error() {
+        let entities = "block wire 1 2 0\ntree 1.5 2.5 3\n";
+        let header = make_chunk_file_header((3, -2), entities);
+        let missing_tree = format!("{}block wire 1 2 0\n", header);
+        for (pos, text) in [
+            ((4, -2), format!("{}{}", header, entities)),
+            ((3, -2), missing_tree),
+        ] {
+            let mut errors = Vec::new();
+            let read = read_chunk_file(pos, "chunk.txt", &text, &mut errors);
+            assert!(read.contains("block wire 1 2 0"));
+            assert_eq!(errors.len(), 1, "{:?}", errors);
+            assert_eq!(errors[0].line, 1);
+            assert!(matches!(
+                errors[0].reason,
+                ParseErrorReason::HeaderMismatch(_)
+            ));
+        }
+    }
+
+    // a chunk loader gives its own chunk and the ones around it a ticket. pending block updates
+    // only do if the world keeps chunks with pending updates
+    #[test]
+    fn chunk_loader_gives_its_chunk_and_radius_tickets() {
+        let mut block_map = make_map();
+        block_map.chunk_loaders.insert((20, -3));
+        let mut updates = UpdateQueue::default();
+        updates.schedule_block_update((100, 100), 1);
+
+        let mut expected = HashSet::new();
+        for i in -CHUNK_LOADER_RADIUS..=CHUNK_LOADER_RADIUS {
+            for j in -CHUNK_LOADER_RADIUS..=CHUNK_LOADER_RADIUS {
+                expected.insert((1 + i, -1 + j));
+            }
+        }
+        assert_eq!(get_chunk_tickets(&block_map, &updates, false), expected);
+        expected.insert((6, 6));
+        assert_eq!(get_chunk_tickets(&block_map, &updates, true), expected);
+    }
+
+    // with the player far away, the blocks in the chunks a chunk loader keeps running are hidden
+    // but stay in the map, while a block just outside its radius is unloaded
+    #[test]
+    fn chunk_loader_chunks_stay_simulated_while_the_player_is_away() {
+        let mut world = World::new();
+        world.spawn((
+            Transform::from_xyz(1000., 1000., 0.),
+            Player {
+                break_cooldown: 0.,
+                attack_cooldown: 0.,
+                place_cooldown: 0.,
+            },
+        ));
+        let mut block_map = make_map();
+        for (pos, block_type) in [
+            ((20, -3), BlockType::ChunkLoader),
+            ((36, -3), BlockType::Wire(0)),
+            ((52, -3), BlockType::Wire(0)),
+        ] {
+            let entity = world
+                .spawn((
+                    Transform::from_xyz(pos.0 as f32, pos.1 as f32, 0.),
+                    Visibility::Inherited,
+                    BlockEntity { pos },
+                ))
+                .id();
+            block_map.blocks.insert(pos, (entity, block_type));
+        }
+        block_map.chunk_loaders.insert((20, -3));
+        world.insert_resource(block_map);
+        world.insert_resource(ChunksToSave {
+            chunks: HashMap::new(),
+            chunks_loaded: HashSet::from([(1, -1), (2, -1), (3, -1)]),
+            chunks_simulated: HashSet::new(),
+        });
+        world.insert_resource(BlockUpdateQueue(UpdateQueue::default()));
+        world.insert_resource(CurrentWorld::default());
+
+        world.run_system_once(unload_far_blocks);
+
+        let block_map = world.resource::<Map>();
+        for pos in [(20, -3), (36, -3)] {
+            let (entity, _) = block_map.blocks[&pos];
+            assert_eq!(
+                world.get::<Visibility>(entity),
+                Some(&Visibility::Hidden),
+                "block at {:?}",
+                pos
+            );
+        }
+        assert!(!block_map.blocks.contains_key(&(52, -3)));
+        let chunks_to_save = world.resource::<ChunksToSave>();
+        assert_eq!(
+            chunks_to_save.chunks_simulated,
+            HashSet::from([(1, -1), (2, -1)])
+        );
+        assert!(chunks_to_save.chunks[&(3, -1)].contains("wire"));
+    }
+
+    // time spent before entering the world doesn't count towards its first autosave
+    #[test]
+    fn autosave_timer_starts_when_the_world_is_entered() {
+        let mut world = World::new();
+        world.insert_resource(Time::<()>::default());
+        world.insert_resource(CurrentWorld::default());
+        world.insert_resource(State::new(AppState::MainMenu));
+        let autosave_seconds = world.resource::<CurrentWorld>().autosave_seconds;
+        // the condition's timer is kept between runs, like it is when it's scheduled
+        let mut condition = IntoSystem::into_system(autosave_is_due);
+        condition.initialize(&mut world);
+        let mut wait = |world: &mut World, seconds: f32| {
+            world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_secs_f32(seconds));
+            condition.run((), world)
+        };
+        assert!(!wait(&mut world, autosave_seconds + 1.));
+
+        world.insert_resource(State::new(AppState::Game));
+        assert!(!wait(&mut world, 2.));
+        assert!(!wait(&mut world, autosave_seconds - 3.));
+        assert!(wait(&mut world, 2.));
+        // and it starts again after each autosave
+        assert!(!wait(&mut world, 1.));
     }
 }
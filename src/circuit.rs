@@ -0,0 +1,935 @@
+// the circuit simulation. this doesn't know anything about bevy, it only works on a grid of
+// block types and a queue of block updates, so it can be run on its own without an App
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+use crate::{
+    BlockType, GateType, BUTTON_PRESS_TICKS, COMPARATOR_SUBTRACT_MODE, DEFAULT_PROCESSOR_DELAY,
+    RAM_ADDRESS_STEP, RAM_SIZE,
+};
+
+// the most block updates processed in one logic tick. whatever is left over is carried to the next tick
+pub const MAX_BLOCK_UPDATES_PER_TICK: usize = 20000;
+// a block updated more often than this in one tick is considered to be part of an update loop
+pub const MAX_UPDATES_PER_BLOCK_PER_TICK: usize = 16;
+// how long blocks in an update loop stay flagged for
+pub const FLAGGED_BLOCK_TICKS: u64 = 40;
+
+// anything that holds a block type per position. the simulation reads and writes blocks through this
+pub trait BlockGrid {
+    fn get_block(&self, pos: (i32, i32)) -> Option<BlockType>;
+    fn set_block(&mut self, pos: (i32, i32), block_type: BlockType);
+}
+
+// the simplest grid, for running circuits without a world around them
+impl BlockGrid for HashMap<(i32, i32), BlockType> {
+    fn get_block(&self, pos: (i32, i32)) -> Option<BlockType> {
+        self.get(&pos).copied()
+    }
+
+    fn set_block(&mut self, pos: (i32, i32), block_type: BlockType) {
+        self.insert(pos, block_type);
+    }
+}
+
+// block updates are processed on every logic tick. updates in `queue` are processed on the
+// current tick, while `scheduled` holds the updates of repeaters and inverters that are switching,
+// keyed by the tick they are due on. `flagged` holds the blocks caught in an update loop, along
+// with the tick they were last caught on. `overloaded` is on while ticks keep running out of
+// updates, so that's only reported once
+#[derive(Default)]
+pub struct UpdateQueue {
+    pub queue: VecDeque<(i32, i32)>,
+    pub scheduled: BTreeMap<u64, Vec<(i32, i32)>>,
+    pub scheduled_positions: HashSet<(i32, i32)>,
+    pub flagged: HashMap<(i32, i32), u64>,
+    pub overloaded: bool,
+    pub tick: u64,
+}
+
+impl UpdateQueue {
+    // updates the block at pos and all its neighbours on the current tick
+    pub fn create_block_update(&mut self, pos: (i32, i32)) {
+        self.queue.push_back(pos);
+        for dir in [0, 2, 1, 3] {
+            self.queue.push_back(get_pos_in_dir(pos, dir));
+        }
+    }
+
+    // schedules an update for the block at pos, delay ticks from now. a block can only have one
+    // scheduled update at a time, so this does nothing if one is already pending
+    pub fn schedule_block_update(&mut self, pos: (i32, i32), delay: i32) {
+        if self.scheduled_positions.contains(&pos) {
+            return;
+        }
+        let due_tick = self.tick + delay.max(1) as u64;
+        self.scheduled_positions.insert(pos);
+        self.scheduled.entry(due_tick).or_default().push(pos);
+    }
+
+    // every position that is waiting for a block update, either this tick or a later one
+    pub fn pending_positions(&self) -> impl Iterator<Item = &(i32, i32)> {
+        self.queue.iter().chain(self.scheduled_positions.iter())
+    }
+
+    // removes and returns all the scheduled updates that are due on or before the current tick
+    fn take_due_block_updates(&mut self) -> Vec<(i32, i32)> {
+        let mut due = vec![];
+        while let Some(entry) = self.scheduled.first_entry() {
+            if *entry.key() > self.tick {
+                break;
+            }
+            due.extend(entry.remove());
+        }
+        for pos in due.iter() {
+            self.scheduled_positions.remove(pos);
+        }
+        due
+    }
+
+    // marks a block as being part of an update loop, so it can be shown to the player
+    fn flag_block_in_update_loop(&mut self, pos: (i32, i32)) {
+        self.flagged.insert(pos, self.tick);
+    }
+}
+
+pub fn get_pos_in_dir(pos: (i32, i32), dir: i32) -> (i32, i32) {
+    match dir {
+        0 => (pos.0, pos.1 + 1),
+        1 => (pos.0 + 1, pos.1),
+        2 => (pos.0, pos.1 - 1),
+        _ => (pos.0 - 1, pos.1),
+    }
+}
+
+// directional wires only conduct along their orientation, so they only connect to the blocks
+// in front of and behind them
+fn conducts_in_dir(wire_dir: i32, dir: i32) -> bool {
+    wire_dir % 2 == dir % 2
+}
+
+// gates, comparators and memory blocks only power the block in front of them. returns the
+// strength of the signal they output (0 to 128) and the direction they're facing, or None for
+// every other block. the front of a block facing dir is the neighbour in direction dir + 2
+fn get_front_output(block_type: BlockType) -> Option<(i32, i32)> {
+    match block_type {
+        BlockType::Gate(_, power, dir, _)
+        | BlockType::SrLatch(power, dir, _)
+        | BlockType::DFlipFlop(power, dir, _, _) => Some((128.min(128 * power), dir)),
+        BlockType::Comparator(power, dir, _) | BlockType::Ram(power, dir, _) => Some((power, dir)),
+        _ => None,
+    }
+}
+
+// the power a wire gets from a neighbouring block, which is in direction inc_dir from the wire
+fn get_power_into_wire(neighbour: BlockType, inc_dir: i32) -> i32 {
+    match neighbour {
+        BlockType::Stone(_) => 128,
+        BlockType::Lever(power) | BlockType::Button(power) | BlockType::PressurePlate(power) => {
+            128 * power
+        }
+        BlockType::Wire(power) => power - 1,
+        BlockType::DirectionalWire(power, dir) if conducts_in_dir(dir, inc_dir) => power - 1,
+        BlockType::Repeater(power, dir, _) | BlockType::Inverter(power, dir, _)
+            if dir != (inc_dir + 2) % 4 =>
+        {
+            128.min(128 * power)
+        }
+        _ => match get_front_output(neighbour) {
+            Some((power, dir)) if dir == inc_dir => power,
+            _ => 0,
+        },
+    }
+}
+
+// the power (0 or 1) going into the block at pos from its neighbour in direction dir
+fn get_processor_input(pos: (i32, i32), dir: i32, grid: &impl BlockGrid) -> i32 {
+    match grid.get_block(get_pos_in_dir(pos, dir)) {
+        Some(BlockType::Stone(_)) => 1,
+        Some(BlockType::Lever(power))
+        | Some(BlockType::Button(power))
+        | Some(BlockType::PressurePlate(power)) => power,
+        Some(BlockType::Wire(power)) => 1.min(power),
+        Some(BlockType::DirectionalWire(power, wire_dir)) if conducts_in_dir(wire_dir, dir) => {
+            1.min(power)
+        }
+        Some(BlockType::Repeater(power, other_dir, _))
+        | Some(BlockType::Inverter(power, other_dir, _))
+            if (other_dir + 2) % 4 != dir =>
+        {
+            power
+        }
+        Some(neighbour) => match get_front_output(neighbour) {
+            Some((power, front_dir)) if front_dir == dir => 1.min(power),
+            _ => 0,
+        },
+        None => 0,
+    }
+}
+
+// like get_processor_input, but keeps the strength of the signal (0 to 128) instead of just
+// whether it's on. wires, comparators and ram carry weaker signals, everything else is full power
+fn get_analog_input(pos: (i32, i32), dir: i32, grid: &impl BlockGrid) -> i32 {
+    match grid.get_block(get_pos_in_dir(pos, dir)) {
+        Some(BlockType::Wire(power)) => power,
+        Some(BlockType::DirectionalWire(power, wire_dir)) if conducts_in_dir(wire_dir, dir) => {
+            power
+        }
+        Some(BlockType::Comparator(power, front_dir, _))
+        | Some(BlockType::Ram(power, front_dir, _))
+            if front_dir == dir =>
+        {
+            power
+        }
+        _ => 128 * get_processor_input(pos, dir, grid),
+    }
+}
+
+// the ram cell selected by the signal strength on the right side of a ram block facing dir
+fn get_ram_address(pos: (i32, i32), dir: i32, grid: &impl BlockGrid) -> usize {
+    ((get_analog_input(pos, (dir + 1) % 4, grid) / RAM_ADDRESS_STEP) as usize).min(RAM_SIZE - 1)
+}
+
+// memory blocks store their inputs as soon as they change, then switch their output after a delay
+// like any other processor. an sr latch is set by its right side and reset by its left. a d flip
+// flop stores the signal behind it when its right side turns on. ram stores the signal behind it
+// in the cell addressed by its right side while its left side is on.
+// returns the block if what it's storing needs to change
+fn get_updated_memory_block(pos: (i32, i32), grid: &impl BlockGrid) -> Option<BlockType> {
+    let block_type = grid.get_block(pos)?;
+    let new_block_type = match block_type {
+        BlockType::SrLatch(power, dir, state) => {
+            let set = get_processor_input(pos, (dir + 1) % 4, grid) > 0;
+            let reset = get_processor_input(pos, (dir + 3) % 4, grid) > 0;
+            let new_state = match (set, reset) {
+                (true, false) => 1,
+                (false, true) => 0,
+                _ => state,
+            };
+            BlockType::SrLatch(power, dir, new_state)
+        }
+        BlockType::DFlipFlop(power, dir, clock, state) => {
+            let new_clock = get_processor_input(pos, (dir + 1) % 4, grid);
+            let new_state = if new_clock > 0 && clock == 0 {
+                get_processor_input(pos, dir, grid)
+            } else {
+                state
+            };
+            BlockType::DFlipFlop(power, dir, new_clock, new_state)
+        }
+        BlockType::Ram(power, dir, mut cells) => {
+            if get_processor_input(pos, (dir + 3) % 4, grid) > 0 {
+                cells[get_ram_address(pos, dir, grid)] = get_analog_input(pos, dir, grid) as u8;
+            }
+            BlockType::Ram(power, dir, cells)
+        }
+        _ => return None,
+    };
+    (new_block_type != block_type).then_some(new_block_type)
+}
+
+// comparators take the signal from behind them and either compare it with, or subtract from it,
+// the stronger of the two signals on their sides
+fn get_comparator_output(mode: i32, rear: i32, side: i32) -> i32 {
+    if mode == COMPARATOR_SUBTRACT_MODE {
+        (rear - side).max(0)
+    } else if rear >= side {
+        rear
+    } else {
+        0
+    }
+}
+
+pub fn get_gate_output(gate_type: GateType, a: bool, b: bool) -> bool {
+    match gate_type {
+        GateType::And => a && b,
+        GateType::Or => a || b,
+        GateType::Xor => a != b,
+        GateType::Nand => !(a && b),
+    }
+}
+
+// the power a processor (a block that switches after a delay, like a repeater, inverter or gate)
+// should be switching to, based on its inputs. repeaters and inverters take their input from
+// behind, gates take theirs from both sides and memory blocks output what they're storing.
+// buttons count as processors that are always switching off, so they turn off a while after
+// being pressed. returns None for every other block
+fn get_processor_target_power(pos: (i32, i32), grid: &impl BlockGrid) -> Option<i32> {
+    match grid.get_block(pos) {
+        Some(BlockType::Repeater(_, dir, _)) => Some(get_processor_input(pos, dir, grid)),
+        Some(BlockType::Inverter(_, dir, _)) => Some(1 - get_processor_input(pos, dir, grid)),
+        Some(BlockType::Gate(gate_type, _, dir, _)) => {
+            let a = get_processor_input(pos, (dir + 1) % 4, grid) > 0;
+            let b = get_processor_input(pos, (dir + 3) % 4, grid) > 0;
+            Some(get_gate_output(gate_type, a, b) as i32)
+        }
+        Some(BlockType::Button(_)) => Some(0),
+        Some(BlockType::SrLatch(_, _, state)) | Some(BlockType::DFlipFlop(_, _, _, state)) => {
+            Some(state)
+        }
+        Some(BlockType::Ram(_, dir, cells)) => Some(cells[get_ram_address(pos, dir, grid)] as i32),
+        Some(BlockType::Comparator(_, dir, mode)) => {
+            let rear = get_analog_input(pos, dir, grid);
+            let side = get_analog_input(pos, (dir + 1) % 4, grid).max(get_analog_input(
+                pos,
+                (dir + 3) % 4,
+                grid,
+            ));
+            Some(get_comparator_output(mode, rear, side))
+        }
+        _ => None,
+    }
+}
+
+// the current power and the delay of a processor. returns None for every other block
+fn get_processor_power_and_delay(block_type: Option<BlockType>) -> Option<(i32, i32)> {
+    match block_type {
+        Some(BlockType::Repeater(power, _, delay))
+        | Some(BlockType::Inverter(power, _, delay))
+        | Some(BlockType::Gate(_, power, _, delay)) => Some((power, delay)),
+        Some(BlockType::Button(power)) => Some((power, BUTTON_PRESS_TICKS)),
+        Some(BlockType::Comparator(power, _, _))
+        | Some(BlockType::SrLatch(power, _, _))
+        | Some(BlockType::DFlipFlop(power, _, _, _))
+        | Some(BlockType::Ram(power, _, _)) => Some((power, DEFAULT_PROCESSOR_DELAY)),
+        _ => None,
+    }
+}
+
+// returns the processor with its power set to power
+fn set_processor_power(block_type: BlockType, power: i32) -> BlockType {
+    match block_type {
+        BlockType::Repeater(_, dir, delay) => BlockType::Repeater(power, dir, delay),
+        BlockType::Inverter(_, dir, delay) => BlockType::Inverter(power, dir, delay),
+        BlockType::Gate(gate_type, _, dir, delay) => BlockType::Gate(gate_type, power, dir, delay),
+        BlockType::Button(_) => BlockType::Button(power),
+        BlockType::Comparator(_, dir, mode) => BlockType::Comparator(power, dir, mode),
+        BlockType::SrLatch(_, dir, state) => BlockType::SrLatch(power, dir, state),
+        BlockType::DFlipFlop(_, dir, clock, state) => {
+            BlockType::DFlipFlop(power, dir, clock, state)
+        }
+        BlockType::Ram(_, dir, cells) => BlockType::Ram(power, dir, cells),
+        _ => block_type,
+    }
+}
+
+// the power a wire is carrying, and the directions it takes power from. returns None if the
+// block isn't a wire
+fn get_wire_power_and_dirs(block_type: Option<BlockType>) -> Option<(i32, Vec<i32>)> {
+    match block_type {
+        Some(BlockType::Wire(power)) => Some((power, vec![0, 1, 2, 3])),
+        Some(BlockType::DirectionalWire(power, dir)) => Some((power, vec![dir % 2, dir % 2 + 2])),
+        _ => None,
+    }
+}
+
+// returns the wire with its power set to power
+fn set_wire_power(block_type: BlockType, power: i32) -> BlockType {
+    match block_type {
+        BlockType::DirectionalWire(_, dir) => BlockType::DirectionalWire(power, dir),
+        _ => BlockType::Wire(power),
+    }
+}
+
+// the wires next to a wire that it's connected to, so power flows between them both ways
+fn get_connected_wires(pos: (i32, i32), grid: &impl BlockGrid) -> Vec<(i32, i32)> {
+    let Some((_, inc_dirs)) = get_wire_power_and_dirs(grid.get_block(pos)) else {
+        return vec![];
+    };
+    inc_dirs
+        .into_iter()
+        .map(|dir| (dir, get_pos_in_dir(pos, dir)))
+        .filter(|(dir, neighbour)| {
+            get_wire_power_and_dirs(grid.get_block(*neighbour))
+                .is_some_and(|(_, neighbour_dirs)| neighbour_dirs.contains(&((dir + 2) % 4)))
+        })
+        .map(|(_, neighbour)| neighbour)
+        .collect()
+}
+
+// updates an entire connected component of wire at once. every wire in it is worked out again from
+// scratch: it starts off unpowered, then power is flooded outward from the blocks powering it,
+// losing one level per wire. this takes as long as the wire is big, however much its power changes,
+// so a tick can go over its budget by one wire line. the blocks next to wires whose power changed
+// are updated afterwards. returns the number of updates used, one per wire
+fn update_wire_line(
+    init_pos: (i32, i32),
+    grid: &mut impl BlockGrid,
+    updates: &mut UpdateQueue,
+) -> usize {
+    let mut line = HashSet::from([init_pos]);
+    let mut to_visit = vec![init_pos];
+    while let Some(pos) = to_visit.pop() {
+        for neighbour in get_connected_wires(pos, grid) {
+            if line.insert(neighbour) {
+                to_visit.push(neighbour);
+            }
+        }
+    }
+
+    // how strongly each wire is powered by the blocks around it that aren't wires in the line
+    let mut powers: HashMap<(i32, i32), i32> = HashMap::new();
+    let mut by_power: Vec<Vec<(i32, i32)>> = vec![];
+    for &pos in line.iter() {
+        let (_, inc_dirs) = get_wire_power_and_dirs(grid.get_block(pos)).unwrap();
+        let mut power = 0;
+        for inc_dir in inc_dirs {
+            let neighbour_pos = get_pos_in_dir(pos, inc_dir);
+            if line.contains(&neighbour_pos) {
+                continue;
+            }
+            if let Some(neighbour) = grid.get_block(neighbour_pos) {
+                power = power.max(get_power_into_wire(neighbour, inc_dir));
+            }
+        }
+        powers.insert(pos, power);
+        if power > 0 {
+            if by_power.len() <= power as usize {
+                by_power.resize(power as usize + 1, vec![]);
+            }
+            by_power[power as usize].push(pos);
+        }
+    }
+    // the strongest wires are spread first, so every wire is only spread from once, at its final
+    // power
+    for power in (2..by_power.len()).rev() {
+        for pos in std::mem::take(&mut by_power[power]) {
+            if powers[&pos] != power as i32 {
+                continue;
+            }
+            for neighbour in get_connected_wires(pos, grid) {
+                if powers[&neighbour] < power as i32 - 1 {
+                    powers.insert(neighbour, power as i32 - 1);
+                    by_power[power - 1].push(neighbour);
+                }
+            }
+        }
+    }
+
+    let mut to_update_next = HashSet::new();
+    for (&pos, &new_power) in powers.iter() {
+        let block_type = grid.get_block(pos).unwrap();
+        let (old_power, _) = get_wire_power_and_dirs(Some(block_type)).unwrap();
+        if new_power != old_power {
+            grid.set_block(pos, set_wire_power(block_type, new_power));
+            for dir in [0, 2, 1, 3] {
+                to_update_next.insert(get_pos_in_dir(pos, dir));
+            }
+        }
+    }
+    for update_pos in to_update_next {
+        if !line.contains(&update_pos) {
+            updates.queue.push_back(update_pos);
+        }
+    }
+    line.len()
+}
+
+// lamps, seven segment displays, conveyors and inserters show their inputs straight away and don't
+// power anything. a lamp, conveyor or inserter is on if any side is powered. a display shows a hex
+// digit, with each side being one bit: the top is 1, the right 2, the bottom 4 and the left 8.
+// returns the block if it needs to change
+fn get_updated_output_block(pos: (i32, i32), grid: &impl BlockGrid) -> Option<BlockType> {
+    match grid.get_block(pos) {
+        Some(BlockType::Lamp(power)) => {
+            let new_power = (0..4).any(|dir| get_processor_input(pos, dir, grid) > 0) as i32;
+            (new_power != power).then_some(BlockType::Lamp(new_power))
+        }
+        Some(BlockType::Conveyor(power, dir)) => {
+            let new_power = (0..4).any(|side| get_processor_input(pos, side, grid) > 0) as i32;
+            (new_power != power).then_some(BlockType::Conveyor(new_power, dir))
+        }
+        Some(BlockType::Inserter(power, dir)) => {
+            let new_power = (0..4).any(|side| get_processor_input(pos, side, grid) > 0) as i32;
+            (new_power != power).then_some(BlockType::Inserter(new_power, dir))
+        }
+        Some(BlockType::SevenSegmentDisplay(value)) => {
+            let new_value = (0..4)
+                .map(|dir| 1.min(get_processor_input(pos, dir, grid)) << dir)
+                .sum();
+            (new_value != value).then_some(BlockType::SevenSegmentDisplay(new_value))
+        }
+        _ => None,
+    }
+}
+
+// runs one logic tick. first switches the processors whose delay is up, then
+// processes block updates until there are none left for this tick, or the tick's update budget
+// runs out. updates left over, and updates of blocks that have been updated too many times this
+// tick, are carried over to the next tick
+pub fn run_logic_tick(grid: &mut impl BlockGrid, updates: &mut UpdateQueue) {
+    updates.tick += 1;
+
+    for pos in updates.take_due_block_updates() {
+        let Some(new_power) = get_processor_target_power(pos, grid) else {
+            continue;
+        };
+        let block_type = grid.get_block(pos).unwrap();
+        if let Some((power, _)) = get_processor_power_and_delay(Some(block_type)) {
+            if power == new_power {
+                continue;
+            }
+        }
+        grid.set_block(pos, set_processor_power(block_type, new_power));
+        updates.create_block_update(pos);
+    }
+
+    let mut updates_this_tick = 0;
+    let mut updates_per_block: HashMap<(i32, i32), usize> = HashMap::new();
+    let mut carried_over = VecDeque::new();
+    let mut ran_out = false;
+
+    while let Some(pos) = updates.queue.pop_front() {
+        if updates_this_tick >= MAX_BLOCK_UPDATES_PER_TICK {
+            updates.queue.push_front(pos);
+            updates.overloaded = true;
+            ran_out = true;
+            break;
+        }
+        let block_updates = updates_per_block.entry(pos).or_insert(0);
+        *block_updates += 1;
+        if *block_updates > MAX_UPDATES_PER_BLOCK_PER_TICK {
+            updates.flag_block_in_update_loop(pos);
+            carried_over.push_back(pos);
+            continue;
+        }
+        updates_this_tick += 1;
+
+        if let Some(new_block_type) = get_updated_memory_block(pos, grid) {
+            grid.set_block(pos, new_block_type);
+        }
+
+        let block_type = grid.get_block(pos);
+        if let Some(BlockType::Wire(_)) | Some(BlockType::DirectionalWire(_, _)) = block_type {
+            updates_this_tick += update_wire_line(pos, grid, updates);
+        } else if let Some((power, delay)) = get_processor_power_and_delay(block_type) {
+            // processors don't switch straight away, they schedule the switch
+            if get_processor_target_power(pos, grid) != Some(power) {
+                updates.schedule_block_update(pos, delay);
+            }
+        } else if let Some(new_block_type) = get_updated_output_block(pos, grid) {
+            grid.set_block(pos, new_block_type);
+        }
+    }
+
+    updates.queue.extend(carried_over);
+    if !ran_out {
+        updates.overloaded = false;
+    }
+    let tick = updates.tick;
+    updates
+        .flagged
+        .retain(|_, flagged_tick| tick - *flagged_tick < FLAGGED_BLOCK_TICKS);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{COMPARATOR_COMPARE_MODE, MAX_PROCESSOR_DELAY};
+
+    type Grid = HashMap<(i32, i32), BlockType>;
+
+    fn run_ticks(grid: &mut Grid, updates: &mut UpdateQueue, ticks: usize) {
+        for _ in 0..ticks {
+            run_logic_tick(grid, updates);
+        }
+    }
+
+    // the power of a processor or a wire
+    fn get_power(grid: &Grid, pos: (i32, i32)) -> i32 {
+        let block_type = grid.get_block(pos);
+        get_processor_power_and_delay(block_type)
+            .or(get_wire_power_and_dirs(block_type).map(|(power, _)| (power, 0)))
+            .unwrap_or_else(|| panic!("{:?} doesn't have a power", block_type))
+            .0
+    }
+
+    // places or takes away a stone, which powers the blocks around it, and updates the blocks
+    // around it
+    fn set_stone(grid: &mut Grid, updates: &mut UpdateQueue, pos: (i32, i32), on: bool) {
+        if on {
+            grid.set_block(pos, BlockType::Stone(0));
+        } else {
+            grid.remove(&pos);
+        }
+        updates.create_block_update(pos);
+    }
+
+    // powers the wire at pos with the given signal strength, by running a wire from it in direction
+    // dir to a stone just far enough away. whatever signal was there before is taken away first
+    fn set_signal(
+        grid: &mut Grid,
+        updates: &mut UpdateQueue,
+        pos: (i32, i32),
+        dir: i32,
+        strength: i32,
+    ) {
+        let mut chain_pos = pos;
+        for _ in 0..=129 {
+            grid.remove(&chain_pos);
+            chain_pos = get_pos_in_dir(chain_pos, dir);
+        }
+        let mut chain_pos = pos;
+        for _ in 0..129 - strength {
+            grid.set_block(chain_pos, BlockType::Wire(0));
+            chain_pos = get_pos_in_dir(chain_pos, dir);
+        }
+        set_stone(grid, updates, chain_pos, strength > 0);
+        updates.create_block_update(pos);
+    }
+
+    // a repeater facing down with a stone behind it switches on delay ticks after the tick the
+    // stone's update is processed on
+    #[test]
+    fn repeater_switches_after_its_delay() {
+        for delay in 1..=MAX_PROCESSOR_DELAY {
+            let mut grid = Grid::new();
+            let mut updates = UpdateQueue::default();
+            grid.set_block((0, 0), BlockType::Repeater(0, 2, delay));
+            set_stone(&mut grid, &mut updates, (0, -1), true);
+
+            run_ticks(&mut grid, &mut updates, delay as usize);
+            assert_eq!(get_power(&grid, (0, 0)), 0, "delay {}", delay);
+            run_ticks(&mut grid, &mut updates, 1);
+            assert_eq!(get_power(&grid, (0, 0)), 1, "delay {}", delay);
+        }
+    }
+
+    // an inverter whose output is wired back around into its input turns itself on and off,
+    // switching every delay ticks
+    #[test]
+    fn inverter_clock_keeps_switching() {
+        for delay in 1..=MAX_PROCESSOR_DELAY {
+            let mut grid = Grid::new();
+            let mut updates = UpdateQueue::default();
+            grid.set_block((0, 0), BlockType::Inverter(1, 0, delay));
+            for pos in [(1, 0), (1, 1), (0, 1)] {
+                grid.set_block(pos, BlockType::Wire(0));
+            }
+            updates.create_block_update((0, 0));
+
+            run_ticks(&mut grid, &mut updates, 1);
+            let mut power = get_power(&grid, (0, 0));
+            for _ in 0..6 {
+                run_ticks(&mut grid, &mut updates, delay as usize - 1);
+                assert_eq!(get_power(&grid, (0, 0)), power, "delay {}", delay);
+                run_ticks(&mut grid, &mut updates, 1);
+                assert_eq!(get_power(&grid, (0, 0)), 1 - power, "delay {}", delay);
+                power = 1 - power;
+            }
+        }
+    }
+
+    // updates past the tick's budget are carried over to the next tick instead of being dropped,
+    // and the overload is over once a tick gets through all of its updates
+    #[test]
+    fn updates_over_the_budget_are_carried_over() {
+        let mut grid = Grid::new();
+        let mut updates = UpdateQueue::default();
+        let extra = 5;
+        for x in 0..(MAX_BLOCK_UPDATES_PER_TICK + extra) as i32 {
+            updates.queue.push_back((x, 0));
+        }
+
+        run_ticks(&mut grid, &mut updates, 1);
+        assert_eq!(updates.queue.len(), extra);
+        assert!(updates.overloaded);
+
+        run_ticks(&mut grid, &mut updates, 1);
+        assert!(updates.queue.is_empty());
+        assert!(!updates.overloaded);
+    }
+
+    // a block that keeps getting updated in the same tick, like one caught in a feedback loop, is
+    // flagged, has its extra updates carried over, and is unflagged a while after the loop stops
+    #[test]
+    fn block_updated_in_a_loop_is_flagged() {
+        let mut grid = Grid::new();
+        let mut updates = UpdateQueue::default();
+        grid.set_block((0, 0), BlockType::Wood);
+        for _ in 0..MAX_UPDATES_PER_BLOCK_PER_TICK + 1 {
+            updates.queue.push_back((0, 0));
+        }
+
+        run_ticks(&mut grid, &mut updates, 1);
+        assert!(updates.flagged.contains_key(&(0, 0)));
+        assert_eq!(updates.queue.len(), 1);
+
+        run_ticks(&mut grid, &mut updates, FLAGGED_BLOCK_TICKS as usize);
+        assert!(updates.flagged.is_empty());
+    }
+
+    // a wire pad big enough that fading it out one level at a time would go over the update budget
+    // turns off in the tick after the stone powering it is taken away
+    #[test]
+    fn large_wire_pad_turns_off_in_one_tick() {
+        let mut grid = Grid::new();
+        let mut updates = UpdateQueue::default();
+        for x in 0..100 {
+            for y in 0..3 {
+                grid.set_block((x, y), BlockType::Wire(0));
+            }
+        }
+        set_stone(&mut grid, &mut updates, (-1, 1), true);
+        run_ticks(&mut grid, &mut updates, 1);
+        assert_eq!(get_power(&grid, (0, 1)), 128);
+        assert_eq!(get_power(&grid, (99, 0)), 128 - 100);
+
+        set_stone(&mut grid, &mut updates, (-1, 1), false);
+        run_ticks(&mut grid, &mut updates, 1);
+        assert!(!updates.overloaded);
+        for x in 0..100 {
+            for y in 0..3 {
+                assert_eq!(get_power(&grid, (x, y)), 0, "wire at {}, {}", x, y);
+            }
+        }
+    }
+
+    // two lines of directional wire running side by side along x carry their own signals. neither
+    // powers the other, or a plain wire next to it, and a stone on the side of one doesn't power it
+    #[test]
+    fn directional_wires_side_by_side_stay_separate() {
+        let mut grid = Grid::new();
+        let mut updates = UpdateQueue::default();
+        for x in 0..10 {
+            grid.set_block((x, 0), BlockType::DirectionalWire(0, 1));
+            grid.set_block((x, 1), BlockType::DirectionalWire(0, 1));
+        }
+        grid.set_block((5, -1), BlockType::Wire(0));
+        set_stone(&mut grid, &mut updates, (-1, 0), true);
+        run_ticks(&mut grid, &mut updates, 1);
+        for x in 0..10 {
+            assert_eq!(get_power(&grid, (x, 0)), 128 - x, "wire at {}, 0", x);
+            assert_eq!(get_power(&grid, (x, 1)), 0, "wire at {}, 1", x);
+        }
+        assert_eq!(get_power(&grid, (5, -1)), 0);
+
+        set_stone(&mut grid, &mut updates, (3, 2), true);
+        run_ticks(&mut grid, &mut updates, 1);
+        for x in 0..10 {
+            assert_eq!(get_power(&grid, (x, 1)), 0, "wire at {}, 1", x);
+        }
+
+        set_stone(&mut grid, &mut updates, (-1, 1), true);
+        run_ticks(&mut grid, &mut updates, 1);
+        for x in 0..10 {
+            assert_eq!(get_power(&grid, (x, 0)), 128 - x, "wire at {}, 0", x);
+            assert_eq!(get_power(&grid, (x, 1)), 128 - x, "wire at {}, 1", x);
+        }
+    }
+
+    // a gate facing down takes its inputs from the stones on its left and right
+    #[test]
+    fn gates_follow_their_truth_tables() {
+        let truth_tables = [
+            (GateType::And, [0, 0, 0, 1]),
+            (GateType::Or, [0, 1, 1, 1]),
+            (GateType::Xor, [0, 1, 1, 0]),
+            (GateType::Nand, [1, 1, 1, 0]),
+        ];
+        for (gate_type, outputs) in truth_tables {
+            for (i, output) in outputs.into_iter().enumerate() {
+                let (a, b) = ((i >> 1) as i32, (i & 1) as i32);
+                let mut grid = Grid::new();
+                let mut updates = UpdateQueue::default();
+                grid.set_block((0, 0), BlockType::Gate(gate_type, 0, 2, 1));
+                set_stone(&mut grid, &mut updates, (-1, 0), a == 1);
+                set_stone(&mut grid, &mut updates, (1, 0), b == 1);
+
+                run_ticks(&mut grid, &mut updates, 3);
+                assert_eq!(
+                    get_power(&grid, (0, 0)),
+                    output,
+                    "{:?} {} {}",
+                    gate_type,
+                    a,
+                    b
+                );
+            }
+        }
+    }
+
+    // a pressure plate powers the wire and the repeater next to it while something stands on it,
+    // and stops as soon as it's stepped off
+    #[test]
+    fn pressure_plate_powers_its_neighbours_while_occupied() {
+        let mut grid = Grid::new();
+        let mut updates = UpdateQueue::default();
+        grid.set_block((0, 0), BlockType::PressurePlate(0));
+        grid.set_block((1, 0), BlockType::Wire(0));
+        grid.set_block((-1, 0), BlockType::Repeater(0, 1, 1));
+        for (occupied, wire_power, repeater_power) in [(1, 128, 1), (0, 0, 0), (1, 128, 1)] {
+            grid.set_block((0, 0), BlockType::PressurePlate(occupied));
+            updates.create_block_update((0, 0));
+            run_ticks(&mut grid, &mut updates, 2);
+            assert_eq!(
+                get_power(&grid, (1, 0)),
+                wire_power,
+                "occupied {}",
+                occupied
+            );
+            assert_eq!(
+                get_power(&grid, (-1, 0)),
+                repeater_power,
+                "occupied {}",
+                occupied
+            );
+        }
+    }
+
+    // a lamp lights up on the tick a stone is put next to it, and goes out when it's taken away
+    #[test]
+    fn lamp_follows_its_power() {
+        let mut grid = Grid::new();
+        let mut updates = UpdateQueue::default();
+        grid.set_block((0, 0), BlockType::Lamp(0));
+        for (on, lamp) in [(true, 1), (false, 0), (true, 1)] {
+            set_stone(&mut grid, &mut updates, (1, 0), on);
+            run_ticks(&mut grid, &mut updates, 1);
+            assert_eq!(
+                grid.get_block((0, 0)),
+                Some(BlockType::Lamp(lamp)),
+                "on {}",
+                on
+            );
+        }
+    }
+
+    // a seven segment display shows every hex digit made by powering its top, right, bottom and
+    // left sides, which are worth 1, 2, 4 and 8
+    #[test]
+    fn seven_segment_display_shows_its_sides_as_a_hex_digit() {
+        let mut grid = Grid::new();
+        let mut updates = UpdateQueue::default();
+        grid.set_block((0, 0), BlockType::SevenSegmentDisplay(0));
+        for value in (0..16).chain([5, 0]) {
+            for dir in 0..4 {
+                let on = (value >> dir) & 1 == 1;
+                set_stone(&mut grid, &mut updates, get_pos_in_dir((0, 0), dir), on);
+            }
+            run_ticks(&mut grid, &mut updates, 1);
+            assert_eq!(
+                grid.get_block((0, 0)),
+                Some(BlockType::SevenSegmentDisplay(value)),
+                "value {}",
+                value
+            );
+        }
+    }
+
+    // a comparator facing down gets its rear signal from below and its side signals from the left
+    // and right. it outputs the signal it works out, at that strength, to the wire in front of it
+    #[test]
+    fn comparator_compares_and_subtracts_signal_strengths() {
+        // mode, rear, left side, right side and the output
+        let cases = [
+            (COMPARATOR_COMPARE_MODE, 100, 0, 0, 100),
+            (COMPARATOR_COMPARE_MODE, 100, 90, 0, 100),
+            (COMPARATOR_COMPARE_MODE, 90, 0, 100, 0),
+            (COMPARATOR_COMPARE_MODE, 90, 90, 60, 90),
+            (COMPARATOR_SUBTRACT_MODE, 100, 30, 0, 70),
+            (COMPARATOR_SUBTRACT_MODE, 100, 20, 45, 55),
+            (COMPARATOR_SUBTRACT_MODE, 30, 0, 100, 0),
+        ];
+        for (mode, rear, left, right, output) in cases {
+            let mut grid = Grid::new();
+            let mut updates = UpdateQueue::default();
+            grid.set_block((0, 0), BlockType::Comparator(0, 2, mode));
+            grid.set_block((0, 1), BlockType::Wire(0));
+            set_signal(&mut grid, &mut updates, (0, -1), 2, rear);
+            set_signal(&mut grid, &mut updates, (-1, 0), 3, left);
+            set_signal(&mut grid, &mut updates, (1, 0), 1, right);
+
+            run_ticks(&mut grid, &mut updates, 3);
+            let case = format!("mode {} rear {} sides {} {}", mode, rear, left, right);
+            assert_eq!(get_power(&grid, (0, 0)), output, "{}", case);
+            assert_eq!(get_power(&grid, (0, 1)), output, "{}", case);
+        }
+    }
+
+    // an sr latch facing down is set by the stone on its left and reset by the one on its right,
+    // and remembers which happened last once both are gone
+    #[test]
+    fn sr_latch_is_set_and_reset() {
+        let mut grid = Grid::new();
+        let mut updates = UpdateQueue::default();
+        grid.set_block((0, 0), BlockType::SrLatch(0, 2, 0));
+        // whether the set and reset stones are there, and the output after placing them
+        for (set, reset, output) in [(1, 0, 1), (0, 0, 1), (0, 1, 0), (0, 0, 0), (1, 0, 1)] {
+            set_stone(&mut grid, &mut updates, (-1, 0), set == 1);
+            set_stone(&mut grid, &mut updates, (1, 0), reset == 1);
+            run_ticks(&mut grid, &mut updates, 3);
+            assert_eq!(
+                get_power(&grid, (0, 0)),
+                output,
+                "set {} reset {}",
+                set,
+                reset
+            );
+        }
+    }
+
+    // a d flip flop facing down stores whether there's a stone below it when the clock stone on its
+    // left is placed, and ignores it at any other time
+    #[test]
+    fn d_flip_flop_stores_its_input_when_the_clock_turns_on() {
+        let mut grid = Grid::new();
+        let mut updates = UpdateQueue::default();
+        grid.set_block((0, 0), BlockType::DFlipFlop(0, 2, 0, 0));
+        // whether the data and clock stones are there, and the output after placing them
+        let steps = [
+            (1, 0, 0),
+            (1, 1, 1),
+            (0, 1, 1),
+            (0, 0, 1),
+            (0, 1, 0),
+            (1, 0, 0),
+        ];
+        for (data, clock, output) in steps {
+            set_stone(&mut grid, &mut updates, (0, -1), data == 1);
+            set_stone(&mut grid, &mut updates, (-1, 0), clock == 1);
+            run_ticks(&mut grid, &mut updates, 3);
+            assert_eq!(
+                get_power(&grid, (0, 0)),
+                output,
+                "data {} clock {}",
+                data,
+                clock
+            );
+        }
+    }
+
+    // ram facing down stores the signal from below in the cell picked by the signal on its left,
+    // while there's a stone on its right, and outputs the picked cell
+    #[test]
+    fn ram_stores_and_reads_back_cells() {
+        let mut grid = Grid::new();
+        let mut updates = UpdateQueue::default();
+        grid.set_block((0, 0), BlockType::Ram(0, 2, [0; RAM_SIZE]));
+        // the address signal and the data written to it
+        let writes = [(3 * RAM_ADDRESS_STEP, 100), (5 * RAM_ADDRESS_STEP + 4, 77)];
+        for (address, data) in writes {
+            set_signal(&mut grid, &mut updates, (-1, 0), 3, address);
+            set_signal(&mut grid, &mut updates, (0, -1), 2, data);
+            run_ticks(&mut grid, &mut updates, 3);
+            set_stone(&mut grid, &mut updates, (1, 0), true);
+            run_ticks(&mut grid, &mut updates, 3);
+            set_stone(&mut grid, &mut updates, (1, 0), false);
+            run_ticks(&mut grid, &mut updates, 3);
+        }
+        // the data changing doesn't change a cell while writing is off
+        set_signal(&mut grid, &mut updates, (0, -1), 2, 10);
+
+        for (address, output) in [(3, 100), (5, 77), (0, 0), (4, 0)] {
+            set_signal(
+                &mut grid,
+                &mut updates,
+                (-1, 0),
+                3,
+                address * RAM_ADDRESS_STEP,
+            );
+            run_ticks(&mut grid, &mut updates, 3);
+            assert_eq!(get_power(&grid, (0, 0)), output, "address {}", address);
+        }
+    }
+}
@@ -0,0 +1,444 @@
+use bevy::prelude::*;
+
+use crate::{
+    get_chunk_of_block, get_pos_in_dir, insert_block_to_inventory, insert_one_into_chest,
+    spawn_dropped_item, take_one_from_chest, BlockGrid, BlockType, ChestInventory, ChunksToSave,
+    DroppedItem, InventorySlot, Map, Player, PlayerInventory,
+};
+
+// how many tiles a second items move along a conveyor
+const CONVEYOR_SPEED: f32 = 2.;
+// how fast items are pulled into the middle of a conveyor from the side, in tiles a second
+const CONVEYOR_CENTERING_SPEED: f32 = 4.;
+
+// where an inserter took an item from, so it can be taken out once it's known there's somewhere to
+// put it
+enum InserterSource {
+    Chest(Entity),
+    DroppedItem(Entity),
+    Player,
+}
+
+// the tile a position in the world is on
+fn get_tile_of_pos(pos: Vec2) -> (i32, i32) {
+    (pos.x.round() as i32, pos.y.round() as i32)
+}
+
+// items can't be moved or dropped onto a tile in a chunk that isn't loaded, since there'd be nowhere
+// to save them without wiping the rest of that chunk
+fn is_tile_loaded(tile: (i32, i32), chunks_to_save: &ChunksToSave) -> bool {
+    chunks_to_save
+        .chunks_loaded
+        .contains(&get_chunk_of_block(tile))
+}
+
+fn get_dir_vec(dir: i32) -> Vec2 {
+    let (x, y) = get_pos_in_dir((0, 0), dir);
+    Vec2::new(x as f32, y as f32)
+}
+
+// items on the ground that are on an unpowered conveyor are carried the way it's facing. they stop
+// in the middle of the last conveyor if there's a block in front of it, so an inserter can pick them
+// up, and fall off the end onto the ground otherwise. the edge of the loaded chunks counts as a block
+pub fn move_items_on_conveyors(
+    mut dropped_items: Query<&mut DroppedItem>,
+    block_map: Res<Map>,
+    chunks_to_save: Res<ChunksToSave>,
+    time: Res<Time>,
+) {
+    for mut dropped_item in &mut dropped_items {
+        let tile = get_tile_of_pos(dropped_item.pos);
+        let Some(BlockType::Conveyor(0, dir)) = block_map.get_block(tile) else {
+            continue;
+        };
+        let forward = get_dir_vec(dir);
+        let offset = dropped_item.pos - Vec2::new(tile.0 as f32, tile.1 as f32);
+        let ahead = offset.dot(forward);
+        let side = offset - forward * ahead;
+
+        let mut new_ahead = ahead + CONVEYOR_SPEED * time.delta_seconds();
+        let next_tile = get_pos_in_dir(tile, dir);
+        let blocked = !is_tile_loaded(next_tile, &chunks_to_save)
+            || matches!(
+                block_map.get_block(next_tile),
+                Some(block_type) if !matches!(block_type, BlockType::Conveyor(..))
+            );
+        if blocked {
+            new_ahead = new_ahead.min(ahead.max(0.));
+        }
+        let side_length = side.length();
+        let new_side = side.normalize_or_zero()
+            * (side_length - CONVEYOR_CENTERING_SPEED * time.delta_seconds()).max(0.);
+
+        dropped_item.pos = Vec2::new(tile.0 as f32, tile.1 as f32) + forward * new_ahead + new_side;
+    }
+}
+
+// finds the items an inserter could take from the tile behind it, in the order it tries them. it
+// takes from a chest, then an item on the ground, then the selected slot of a player standing
+// there. every stack in a chest is offered, so one that doesn't fit in front doesn't hold up the
+// rest
+fn find_items_for_inserter(
+    pos: (i32, i32),
+    block_map: &Map,
+    chests: &Query<&mut ChestInventory>,
+    dropped_items: &Query<(&mut DroppedItem, Entity)>,
+    player_tile: (i32, i32),
+    inventory: &PlayerInventory,
+) -> Vec<(InserterSource, InventorySlot)> {
+    if let Some(&(entity, BlockType::Chest)) = block_map.blocks.get(&pos) {
+        let Ok(chest) = chests.get(entity) else {
+            return vec![];
+        };
+        return chest
+            .slots
+            .iter()
+            .flatten()
+            .map(|slot| (InserterSource::Chest(entity), slot.clone()))
+            .collect();
+    }
+    if let Some((dropped_item, entity)) = dropped_items.iter().find(|(dropped_item, _)| {
+        dropped_item.slot.count > 0 && get_tile_of_pos(dropped_item.pos) == pos
+    }) {
+        return vec![(
+            InserterSource::DroppedItem(entity),
+            dropped_item.slot.clone(),
+        )];
+    }
+    if player_tile == pos {
+        if let Some(Some(slot)) = inventory.slots.get(inventory.selected_slot) {
+            return vec![(InserterSource::Player, slot.clone())];
+        }
+    }
+    vec![]
+}
+
+// every half a second, each unpowered inserter moves one item from the tile behind it to the tile in
+// front of it. items go into a chest or a player standing in front, or onto the ground if there's
+// nothing there but a conveyor and its chunk is loaded
+#[allow(clippy::too_many_arguments)]
+pub fn inserter_system(
+    mut commands: Commands,
+    block_map: Res<Map>,
+    chunks_to_save: Res<ChunksToSave>,
+    mut chests: Query<&mut ChestInventory>,
+    mut dropped_items: Query<(&mut DroppedItem, Entity)>,
+    player: Query<&Transform, With<Player>>,
+    mut inventory: ResMut<PlayerInventory>,
+    asset_server: Res<AssetServer>,
+) {
+    let player_tile = get_tile_of_pos(player.get_single().unwrap().translation.truncate());
+    for &pos in block_map.inserters.iter() {
+        let Some(BlockType::Inserter(0, dir)) = block_map.get_block(pos) else {
+            continue;
+        };
+        let items = find_items_for_inserter(
+            get_pos_in_dir(pos, (dir + 2) % 4),
+            &block_map,
+            &chests,
+            &dropped_items,
+            player_tile,
+            &inventory,
+        );
+        let front = get_pos_in_dir(pos, dir);
+        for (source, slot) in items {
+            let item = InventorySlot { count: 1, ..slot };
+            let put = match block_map.get_block(front) {
+                Some(BlockType::Chest) => {
+                    let chest_entity = block_map.blocks.get(&front).unwrap().0;
+                    match chests.get_mut(chest_entity) {
+                        Ok(mut chest) => insert_one_into_chest(&mut chest, &item),
+                        Err(_) => false,
+                    }
+                }
+                _ if player_tile == front => {
+                    inventory.has_room_for(item.item_type, &item.data)
+                        && insert_block_to_inventory(
+                            &mut inventory,
+                            item.item_type,
+                            1,
+                            item.data.clone(),
+                        ) == 0
+                }
+                // it's added to an item that's already there if it can be, so they don't pile up
+                None | Some(BlockType::Conveyor(..)) if is_tile_loaded(front, &chunks_to_save) => {
+                    let stacked_on = dropped_items.iter_mut().find(|(dropped_item, _)| {
+                        get_tile_of_pos(dropped_item.pos) == front
+                            && dropped_item.slot.count > 0
+                            && dropped_item.slot.count < dropped_item.slot.max_stack
+                            && dropped_item.slot.can_stack_with(item.item_type, &item.data)
+                    });
+                    if let Some((mut dropped_item, _)) = stacked_on {
+                        dropped_item.slot.count += 1;
+                    } else {
+                        spawn_dropped_item(
+                            &mut commands,
+                            &asset_server,
+                            Vec2::new(front.0 as f32, front.1 as f32),
+                            item.clone(),
+                            0.,
+                        );
+                    }
+                    true
+                }
+                _ => false,
+            };
+            if !put {
+                continue;
+            }
+
+            match source {
+                InserterSource::Chest(entity) => {
+                    if let Ok(mut chest) = chests.get_mut(entity) {
+                        take_one_from_chest(&mut chest, &item);
+                    }
+                }
+                InserterSource::DroppedItem(entity) => {
+                    if let Ok((mut dropped_item, _)) = dropped_items.get_mut(entity) {
+                        dropped_item.slot.count -= 1;
+                        if dropped_item.slot.count == 0 {
+                            commands.entity(entity).despawn();
+                        }
+                    }
+                }
+                InserterSource::Player => {
+                    let selected_slot = inventory.selected_slot;
+                    if let Some(slot) = inventory.slots.get_mut(selected_slot) {
+                        if let Some(item_slot) = slot.as_mut() {
+                            item_slot.count -= 1;
+                            if item_slot.count == 0 {
+                                *slot = None;
+                            }
+                        }
+                    }
+                }
+            }
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ItemType, INVENTORY_SIZE};
+    use bevy::ecs::system::RunSystemOnce;
+    use std::collections::{HashMap, HashSet};
+    use std::time::Duration;
+
+    fn make_map() -> Map {
+        Map {
+            blocks: HashMap::new(),
+            pressure_plates: HashSet::new(),
+            chunk_loaders: HashSet::new(),
+            inserters: HashSet::new(),
+        }
+    }
+
+    fn make_chunks_to_save() -> ChunksToSave {
+        ChunksToSave {
+            chunks: HashMap::new(),
+            chunks_loaded: HashSet::from([(0, 0), (-1, 0)]),
+            chunks_simulated: HashSet::new(),
+        }
+    }
+
+    fn spawn_item(world: &mut World, pos: Vec2, item_type: ItemType, count: usize) -> Entity {
+        world
+            .spawn(DroppedItem {
+                pos,
+                slot: InventorySlot::new(item_type, count, None),
+                pickup_cooldown: 0.,
+            })
+            .id()
+    }
+
+    fn spawn_chest(
+        world: &mut World,
+        block_map: &mut Map,
+        pos: (i32, i32),
+        chest: ChestInventory,
+    ) -> Entity {
+        let entity = world.spawn(chest).id();
+        block_map.blocks.insert(pos, (entity, BlockType::Chest));
+        entity
+    }
+
+    fn player() -> Player {
+        Player {
+            break_cooldown: 0.,
+            attack_cooldown: 0.,
+            place_cooldown: 0.,
+        }
+    }
+
+    // inserter_system needs an asset server for the items it drops, which only an app can make
+    fn make_inserter_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()));
+        app.insert_resource(make_chunks_to_save());
+        app.insert_resource(PlayerInventory {
+            selected_slot: 0,
+            slots: vec![None; INVENTORY_SIZE],
+            held_slot: None,
+        });
+        app
+    }
+
+    fn chest_contents(world: &World, entity: Entity) -> Vec<(ItemType, usize)> {
+        world
+            .get::<ChestInventory>(entity)
+            .unwrap()
+            .slots
+            .iter()
+            .flatten()
+            .map(|slot| (slot.item_type, slot.count))
+            .collect()
+    }
+
+    fn item_pos(world: &World, entity: Entity) -> Vec2 {
+        world.get::<DroppedItem>(entity).unwrap().pos
+    }
+
+    // items are carried a bit further each tick and pulled into the middle, until they stop at the
+    // end of the line in front of a block. items on a powered conveyor don't move
+    #[test]
+    fn items_move_along_unpowered_conveyors() {
+        let mut world = World::new();
+        let mut block_map = make_map();
+        for x in 0..3 {
+            block_map
+                .blocks
+                .insert((x, 0), (Entity::PLACEHOLDER, BlockType::Conveyor(0, 1)));
+        }
+        block_map
+            .blocks
+            .insert((0, 5), (Entity::PLACEHOLDER, BlockType::Conveyor(1, 1)));
+        spawn_chest(
+            &mut world,
+            &mut block_map,
+            (3, 0),
+            ChestInventory::default(),
+        );
+        world.insert_resource(block_map);
+        world.insert_resource(make_chunks_to_save());
+        world.insert_resource(Time::<()>::default());
+        let moving = spawn_item(&mut world, Vec2::new(0., 0.2), ItemType::Wood, 1);
+        let at_the_end = spawn_item(&mut world, Vec2::new(2., 0.), ItemType::Wood, 1);
+        let on_powered = spawn_item(&mut world, Vec2::new(0., 5.), ItemType::Wood, 1);
+
+        let tick = |world: &mut World| {
+            world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_millis(100));
+            world.run_system_once(move_items_on_conveyors);
+        };
+        tick(&mut world);
+        assert!(item_pos(&world, moving).distance(Vec2::new(0.2, 0.)) < 0.001);
+        assert_eq!(item_pos(&world, at_the_end), Vec2::new(2., 0.));
+        assert_eq!(item_pos(&world, on_powered), Vec2::new(0., 5.));
+
+        tick(&mut world);
+        assert!(item_pos(&world, moving).distance(Vec2::new(0.4, 0.)) < 0.001);
+        for _ in 0..20 {
+            tick(&mut world);
+        }
+        assert!(item_pos(&world, moving).distance(Vec2::new(2., 0.)) < 0.001);
+        assert_eq!(item_pos(&world, at_the_end), Vec2::new(2., 0.));
+        assert_eq!(item_pos(&world, on_powered), Vec2::new(0., 5.));
+    }
+
+    // a powered inserter leaves the chest behind it alone
+    #[test]
+    fn powered_inserters_dont_move_items() {
+        let mut app = make_inserter_app();
+        let mut block_map = make_map();
+        let mut full = ChestInventory::default();
+        full.slots[0] = Some(InventorySlot::new(ItemType::Wood, 5, None));
+        let from = spawn_chest(&mut app.world, &mut block_map, (-1, 0), full);
+        let to = spawn_chest(
+            &mut app.world,
+            &mut block_map,
+            (1, 0),
+            ChestInventory::default(),
+        );
+        block_map
+            .blocks
+            .insert((0, 0), (Entity::PLACEHOLDER, BlockType::Inserter(1, 1)));
+        block_map.inserters.insert((0, 0));
+        app.world.insert_resource(block_map);
+        app.world.spawn((Transform::from_xyz(0., 8., 0.), player()));
+
+        app.world.run_system_once(inserter_system);
+        assert_eq!(chest_contents(&app.world, from), vec![(ItemType::Wood, 5)]);
+        assert_eq!(chest_contents(&app.world, to), vec![]);
+
+        // the same inserter without power moves one
+        app.world
+            .resource_mut::<Map>()
+            .blocks
+            .insert((0, 0), (Entity::PLACEHOLDER, BlockType::Inserter(0, 1)));
+        app.world.run_system_once(inserter_system);
+        assert_eq!(chest_contents(&app.world, from), vec![(ItemType::Wood, 4)]);
+        assert_eq!(chest_contents(&app.world, to), vec![(ItemType::Wood, 1)]);
+    }
+
+    // with a chest, an item on the ground and the player all behind it, an inserter only takes
+    // from the chest. once the chest is gone it takes the item on the ground, and once that's gone
+    // the player's selected item, one item a tick
+    #[test]
+    fn inserters_take_from_chests_then_items_then_the_player() {
+        let mut app = make_inserter_app();
+        let mut block_map = make_map();
+        let mut from = ChestInventory::default();
+        from.slots[3] = Some(InventorySlot::new(ItemType::Wood, 2, None));
+        let from = spawn_chest(&mut app.world, &mut block_map, (-1, 0), from);
+        let to = spawn_chest(
+            &mut app.world,
+            &mut block_map,
+            (1, 0),
+            ChestInventory::default(),
+        );
+        block_map
+            .blocks
+            .insert((0, 0), (Entity::PLACEHOLDER, BlockType::Inserter(0, 1)));
+        block_map.inserters.insert((0, 0));
+        app.world.insert_resource(block_map);
+        let item = spawn_item(&mut app.world, Vec2::new(-1., 0.), ItemType::Copper, 1);
+        app.world
+            .spawn((Transform::from_xyz(-1., 0., 0.), player()));
+        app.world.resource_mut::<PlayerInventory>().slots[0] =
+            Some(InventorySlot::new(ItemType::Iron, 2, None));
+
+        app.world.run_system_once(inserter_system);
+        assert_eq!(chest_contents(&app.world, from), vec![(ItemType::Wood, 1)]);
+        assert_eq!(chest_contents(&app.world, to), vec![(ItemType::Wood, 1)]);
+        assert!(app.world.get_entity(item).is_some());
+
+        app.world.resource_mut::<Map>().blocks.remove(&(-1, 0));
+        app.world.run_system_once(inserter_system);
+        assert_eq!(
+            chest_contents(&app.world, to),
+            vec![(ItemType::Wood, 1), (ItemType::Copper, 1)]
+        );
+        assert!(app.world.get_entity(item).is_none());
+        assert_eq!(
+            app.world.resource::<PlayerInventory>().slots[0],
+            Some(InventorySlot::new(ItemType::Iron, 2, None))
+        );
+
+        app.world.run_system_once(inserter_system);
+        assert_eq!(
+            chest_contents(&app.world, to),
+            vec![
+                (ItemType::Wood, 1),
+                (ItemType::Copper, 1),
+                (ItemType::Iron, 1)
+            ]
+        );
+        assert_eq!(
+            app.world.resource::<PlayerInventory>().slots[0],
+            Some(InventorySlot::new(ItemType::Iron, 1, None))
+        );
+    }
+}
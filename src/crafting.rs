@@ -0,0 +1,186 @@
+use bevy::prelude::*;
+
+use std::fs;
+
+use crate::{
+    get_item_type_from_string_rep, get_string_rep_from_item_type, insert_block_to_inventory,
+    parse_word, ItemType, LoadErrors, ParseError, ParseErrorReason, PlayerInventory,
+};
+
+// the recipes are read from here when the game starts, so they can be changed without rebuilding
+// the game
+const RECIPES_FILE: &str = "./assets/recipes.txt";
+
+#[derive(Clone, Debug)]
+pub struct Recipe {
+    pub output: ItemType,
+    pub count: usize,
+    pub inputs: Vec<(ItemType, usize)>,
+}
+
+#[derive(Resource, Default)]
+pub struct Recipes(pub Vec<Recipe>);
+
+fn get_item_name(item_type: ItemType) -> String {
+    get_string_rep_from_item_type(Some(item_type)).0
+}
+
+// reads items written as a count and a name, separated by +, like "2 wire + 1 stone"
+fn get_items_from_string_rep(text: &str) -> Result<Vec<(ItemType, usize)>, ParseErrorReason> {
+    text.split('+')
+        .map(|item| {
+            let parts = item.split_whitespace().collect::<Vec<&str>>();
+            let count = parse_word::<usize>(&parts, 0)?;
+            let name = parts.get(1).ok_or(ParseErrorReason::TooShort)?;
+            Ok((get_item_type_from_string_rep(name, &[])?, count))
+        })
+        .collect()
+}
+
+// a recipe is written like "1 repeater = 2 wire + 1 stone"
+pub fn get_recipe_from_string_rep(line: &str) -> Result<Recipe, ParseErrorReason> {
+    let (output, inputs) = line.split_once('=').ok_or(ParseErrorReason::TooShort)?;
+    let (output, count) = match get_items_from_string_rep(output)?[..] {
+        [output] => output,
+        _ => return Err(ParseErrorReason::BadWord(String::from(output.trim()))),
+    };
+    Ok(Recipe {
+        output,
+        count,
+        inputs: get_items_from_string_rep(inputs)?,
+    })
+}
+
+pub fn make_string_rep_of_recipe(recipe: &Recipe) -> String {
+    let inputs = recipe
+        .inputs
+        .iter()
+        .map(|(item_type, count)| format!("{} {}", count, get_item_name(*item_type)))
+        .collect::<Vec<String>>();
+    format!(
+        "{} {} = {}",
+        recipe.count,
+        get_item_name(recipe.output),
+        inputs.join(" + ")
+    )
+}
+
+pub fn load_recipes(mut recipes: ResMut<Recipes>, mut load_errors: ResMut<LoadErrors>) {
+    let Ok(recipes_string) = fs::read_to_string(RECIPES_FILE) else {
+        println!("couldn't read {}, so nothing can be crafted", RECIPES_FILE);
+        return;
+    };
+    let mut errors = Vec::new();
+    for (i, line) in recipes_string.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match get_recipe_from_string_rep(line) {
+            Ok(recipe) => recipes.0.push(recipe),
+            Err(reason) => errors.push(ParseError {
+                file: String::from(RECIPES_FILE),
+                line: i + 1,
+                reason,
+            }),
+        }
+    }
+    load_errors.add_file_errors(RECIPES_FILE, &recipes_string, errors);
+}
+
+pub fn can_craft(inventory: &PlayerInventory, recipe: &Recipe) -> bool {
+    recipe
+        .inputs
+        .iter()
+        .all(|(item_type, count)| inventory.count(*item_type) >= *count)
+}
+
+// crafts a recipe once. it's tried on a copy of the inventory first, so nothing is used up if there
+// isn't enough of something or there's no room for what's made
+pub fn craft(inventory: &mut PlayerInventory, recipe: &Recipe) -> Result<(), String> {
+    let mut crafted = inventory.clone();
+    for (item_type, count) in recipe.inputs.iter() {
+        if !crafted.remove(*item_type, *count) {
+            return Err(format!("not enough {}", get_item_name(*item_type)));
+        }
+    }
+    if insert_block_to_inventory(&mut crafted, recipe.output, recipe.count, None) > 0 {
+        return Err(String::from("there's no room in your inventory"));
+    }
+    *inventory = crafted;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InventorySlot, DEFAULT_MAX_STACK, INVENTORY_SIZE};
+
+    fn make_inventory(slots: Vec<Option<InventorySlot>>) -> PlayerInventory {
+        let mut inventory = PlayerInventory {
+            selected_slot: 0,
+            slots,
+            held_slot: None,
+        };
+        inventory.slots.resize(INVENTORY_SIZE, None);
+        inventory
+    }
+
+    #[test]
+    fn recipes_are_written_the_way_they_are_read() {
+        for line in [
+            "4 wire = 1 redstone_dust",
+            "1 repeater = 2 wire + 1 stone",
+            "1 nand_gate = 1 and_gate + 1 inverter",
+        ] {
+            let recipe = get_recipe_from_string_rep(line).unwrap();
+            assert_eq!(make_string_rep_of_recipe(&recipe), line);
+        }
+    }
+
+    #[test]
+    fn recipe_without_an_output_name_is_an_error() {
+        assert_eq!(
+            get_recipe_from_string_rep("1 = 2 wire").err(),
+            Some(ParseErrorReason::TooShort)
+        );
+        assert!(get_recipe_from_string_rep("1 wire 2 stone").is_err());
+        assert!(get_recipe_from_string_rep("1 wire = 2 not_an_item").is_err());
+    }
+
+    #[test]
+    fn failed_craft_leaves_the_inventory_untouched() {
+        let recipe = get_recipe_from_string_rep("1 repeater = 2 wire + 1 stone").unwrap();
+
+        // there's enough wire, but no stone
+        let mut inventory =
+            make_inventory(vec![Some(InventorySlot::new(ItemType::Wire(0), 5, None))]);
+        let slots = inventory.slots.clone();
+        assert!(craft(&mut inventory, &recipe).is_err());
+        assert_eq!(inventory.slots, slots);
+
+        // every slot is still full after the inputs are used, so there's nowhere to put the
+        // repeater
+        let mut full_slots = vec![
+            Some(InventorySlot::new(ItemType::Wire(0), 3, None)),
+            Some(InventorySlot::new(ItemType::Stone(0), 2, None)),
+        ];
+        full_slots.resize(
+            INVENTORY_SIZE,
+            Some(InventorySlot::new(ItemType::Wood, DEFAULT_MAX_STACK, None)),
+        );
+        let mut inventory = make_inventory(full_slots);
+        let slots = inventory.slots.clone();
+        assert!(craft(&mut inventory, &recipe).is_err());
+        assert_eq!(inventory.slots, slots);
+
+        // and with room it's made
+        let mut inventory = make_inventory(vec![
+            Some(InventorySlot::new(ItemType::Wire(0), 2, None)),
+            Some(InventorySlot::new(ItemType::Stone(0), 1, None)),
+        ]);
+        assert!(craft(&mut inventory, &recipe).is_ok());
+        assert_eq!(inventory.count(ItemType::Wire(0)), 0);
+        assert_eq!(inventory.count(ItemType::Stone(0)), 0);
+        assert_eq!(inventory.count(recipe.output), 1);
+    }
+}
@@ -0,0 +1,234 @@
+use bevy::prelude::*;
+
+use crate::{
+    get_item_color, get_item_texture, get_slot_from_string_rep, insert_block_to_inventory,
+    make_string_rep_of_slot, parse_word, InventorySlot, MousePosInWorld, ParseErrorReason, Player,
+    PlayerInventory,
+};
+
+// how close the player has to be to an item on the ground to pick it up
+const PICKUP_RADIUS: f32 = 1.;
+// items thrown with q can't be picked up again for this long, or they'd come straight back
+const THROWN_PICKUP_COOLDOWN: f32 = 1.5;
+// how far in front of the player thrown items land
+const THROW_DISTANCE: f32 = 1.5;
+
+// a stack of items lying on the ground. they're dropped with q, and when something is broken or
+// gathered but there's no room for it in the inventory
+#[derive(Component)]
+pub struct DroppedItem {
+    pub pos: Vec2,
+    pub slot: InventorySlot,
+    pub pickup_cooldown: f32,
+}
+
+pub fn spawn_dropped_item(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    pos: Vec2,
+    slot: InventorySlot,
+    pickup_cooldown: f32,
+) {
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: get_item_color(slot.item_type),
+                custom_size: Some(Vec2::splat(0.5)),
+                ..default()
+            },
+            transform: Transform::from_xyz(pos.x, pos.y, 0.02),
+            texture: get_item_texture(slot.item_type, asset_server),
+            ..default()
+        },
+        DroppedItem {
+            pos,
+            slot,
+            pickup_cooldown,
+        },
+    ));
+}
+
+// written like "item x y wire 5", with the item's data after the count like an inventory slot
+pub fn make_string_rep_of_dropped_item(dropped_item: &DroppedItem) -> String {
+    format!(
+        "item {} {} {}\n",
+        dropped_item.pos.x,
+        dropped_item.pos.y,
+        make_string_rep_of_slot(&Some(dropped_item.slot.clone()))
+    )
+}
+
+// reads where an item line's item is and what it is, or None if the line is for an empty slot
+pub fn get_dropped_item_from_string_rep(
+    parts: &[&str],
+) -> Result<Option<(Vec2, InventorySlot)>, ParseErrorReason> {
+    let x = parse_word::<f32>(parts, 1)?;
+    let y = parse_word::<f32>(parts, 2)?;
+    // get_slot_from_string_rep skips the first word it's given, so the slice starts at the y
+    // position to line the item's name up with where it reads it from
+    let slot = get_slot_from_string_rep(&parts[2..])?;
+    Ok(slot.map(|slot| (Vec2::new(x, y), slot)))
+}
+
+pub fn spawn_dropped_item_from_string_rep(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    parts: Vec<&str>, // a vector of strings, containing the words in the string representation
+) -> Result<(), ParseErrorReason> {
+    if let Some((pos, slot)) = get_dropped_item_from_string_rep(&parts)? {
+        spawn_dropped_item(commands, asset_server, pos, slot, 0.);
+    }
+    Ok(())
+}
+
+// q throws one of the selected item in front of the player, and shift q throws the whole stack
+pub fn drop_item_system(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut inventory: ResMut<PlayerInventory>,
+    player: Query<&Transform, With<Player>>,
+    mouse_world: Res<MousePosInWorld>,
+    asset_server: Res<AssetServer>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::Q) {
+        return;
+    }
+    let player_pos = player.get_single().unwrap().translation.truncate();
+    let whole_stack =
+        keyboard_input.pressed(KeyCode::ShiftLeft) || keyboard_input.pressed(KeyCode::ShiftRight);
+    let selected_slot = inventory.selected_slot;
+    let Some(slot) = inventory.slots.get_mut(selected_slot) else {
+        return;
+    };
+    let Some(item_slot) = slot.as_mut() else {
+        return;
+    };
+    let count = if whole_stack { item_slot.count } else { 1 };
+    let thrown = InventorySlot {
+        count,
+        ..item_slot.clone()
+    };
+    item_slot.count -= count;
+    if item_slot.count == 0 {
+        *slot = None;
+    }
+    let direction = (mouse_world.pos - player_pos).normalize_or_zero();
+    spawn_dropped_item(
+        &mut commands,
+        &asset_server,
+        player_pos + direction * THROW_DISTANCE,
+        thrown,
+        THROWN_PICKUP_COOLDOWN,
+    );
+}
+
+// items on the ground bob up and down a little, so they stand out from blocks. their sprite follows
+// their position, since conveyors move them
+pub fn update_dropped_items(
+    mut dropped_items: Query<(&mut Transform, &mut DroppedItem)>,
+    time: Res<Time>,
+) {
+    for (mut transform, mut dropped_item) in &mut dropped_items {
+        dropped_item.pickup_cooldown =
+            (dropped_item.pickup_cooldown - time.delta_seconds()).max(0.);
+        let bob = (time.elapsed_seconds() * 3. + dropped_item.pos.x).sin() * 0.08;
+        transform.translation.x = dropped_item.pos.x;
+        transform.translation.y = dropped_item.pos.y + bob;
+    }
+}
+
+// the player picks up items just by walking over them. if only some of a stack fits, the rest stays
+// on the ground
+pub fn pick_up_dropped_items(
+    mut commands: Commands,
+    mut dropped_items: Query<(&mut DroppedItem, Entity)>,
+    player: Query<&Transform, With<Player>>,
+    mut inventory: ResMut<PlayerInventory>,
+) {
+    let player_pos = player.get_single().unwrap().translation.truncate();
+    for (mut dropped_item, entity) in &mut dropped_items {
+        if dropped_item.pickup_cooldown > 0.
+            || dropped_item.pos.distance(player_pos) > PICKUP_RADIUS
+        {
+            continue;
+        }
+        if pick_up_dropped_item(&mut inventory, &mut dropped_item) {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+// puts as much of a dropped item in the inventory as fits, leaving the rest of it on the ground.
+// returns whether all of it was picked up
+fn pick_up_dropped_item(inventory: &mut PlayerInventory, dropped_item: &mut DroppedItem) -> bool {
+    let slot = &dropped_item.slot;
+    if !inventory.has_room_for(slot.item_type, &slot.data) {
+        return false;
+    }
+    dropped_item.slot.count =
+        insert_block_to_inventory(inventory, slot.item_type, slot.count, slot.data.clone());
+    dropped_item.slot.count == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ItemType, INVENTORY_SIZE};
+
+    fn stack(item_type: ItemType, count: usize) -> Option<InventorySlot> {
+        Some(InventorySlot::new(item_type, count, None))
+    }
+
+    fn full_inventory() -> PlayerInventory {
+        PlayerInventory {
+            selected_slot: 0,
+            slots: vec![stack(ItemType::Stone(0), 64); INVENTORY_SIZE],
+            held_slot: None,
+        }
+    }
+
+    fn dropped(item_type: ItemType, count: usize) -> DroppedItem {
+        DroppedItem {
+            pos: Vec2::ZERO,
+            slot: InventorySlot::new(item_type, count, None),
+            pickup_cooldown: 0.,
+        }
+    }
+
+    // the item's data comes after its count, and can have spaces in it
+    #[test]
+    fn item_lines_round_trip() {
+        let dropped_item = DroppedItem {
+            pos: Vec2::new(-3.5, 7.25),
+            slot: InventorySlot::new(ItemType::Ram, 1, Some(String::from("1 2 3"))),
+            pickup_cooldown: 0.,
+        };
+        let line = make_string_rep_of_dropped_item(&dropped_item);
+        assert_eq!(line, "item -3.5 7.25 ram 1 1 2 3\n");
+        let parts = line.trim_end().split(' ').collect::<Vec<&str>>();
+        assert_eq!(
+            get_dropped_item_from_string_rep(&parts),
+            Ok(Some((dropped_item.pos, dropped_item.slot)))
+        );
+    }
+
+    #[test]
+    fn picking_up_fills_what_room_there_is() {
+        let mut inventory = full_inventory();
+        let mut wood = dropped(ItemType::Wood, 5);
+        assert!(!pick_up_dropped_item(&mut inventory, &mut wood));
+        assert_eq!(wood.slot.count, 5);
+
+        // only the 4 more that fit on the last stack are picked up
+        inventory.slots[3] = stack(ItemType::Stone(0), 60);
+        let mut stone = dropped(ItemType::Stone(0), 10);
+        assert!(!pick_up_dropped_item(&mut inventory, &mut stone));
+        assert_eq!(stone.slot.count, 6);
+        assert_eq!(inventory.slots[3], stack(ItemType::Stone(0), 64));
+
+        inventory.slots[3] = None;
+        assert!(pick_up_dropped_item(&mut inventory, &mut stone));
+        assert_eq!(stone.slot.count, 0);
+        assert_eq!(inventory.slots[3], stack(ItemType::Stone(0), 6));
+    }
+}
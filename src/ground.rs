@@ -0,0 +1,259 @@
+use bevy::{
+    prelude::*,
+    render::{mesh::Indices, render_resource::PrimitiveTopology},
+    sprite::MaterialMesh2dBundle,
+};
+use std::collections::HashMap;
+
+use crate::{
+    get_biome, get_fractal_noise, get_height, get_tile_shade, Biome, ChunkBackground,
+    ParseErrorReason, BEACH_HEIGHT, DIRT_PATCH_SALT,
+};
+
+// how much more drag there is in water than on dry ground, so wading through lakes is slow
+const WATER_DRAG: f32 = 4.;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GroundTile {
+    Grass,
+    Sand,
+    Water,
+    Dirt,
+}
+
+// the ground of every loaded chunk, as 16 rows of 16 tiles starting from the bottom left. tiles are
+// centered on whole positions, like blocks, so the ground under a block has the block's position
+#[derive(Resource, Default)]
+pub struct GroundMap {
+    pub chunks: HashMap<(i32, i32), [GroundTile; 256]>,
+}
+
+impl GroundMap {
+    // the ground at a position in the world, or None if its chunk isn't loaded
+    pub fn get_tile(&self, pos: Vec2) -> Option<GroundTile> {
+        let tile = (pos.x.round() as i32, pos.y.round() as i32);
+        let tiles = self
+            .chunks
+            .get(&(tile.0.div_euclid(16), tile.1.div_euclid(16)))?;
+        Some(tiles[(tile.1.rem_euclid(16) * 16 + tile.0.rem_euclid(16)) as usize])
+    }
+}
+
+pub fn get_ground_tile_color(tile: GroundTile) -> Color {
+    match tile {
+        GroundTile::Grass => Color::rgb(0.25, 0.42, 0.13),
+        GroundTile::Sand => Color::rgb(0.8, 0.7, 0.42),
+        GroundTile::Water => Color::rgb(0.15, 0.32, 0.6),
+        GroundTile::Dirt => Color::rgb(0.4, 0.3, 0.18),
+    }
+}
+
+// multiplies the drag on anything moving over this ground
+pub fn get_ground_drag(tile: Option<GroundTile>) -> f32 {
+    match tile {
+        Some(GroundTile::Water) => WATER_DRAG,
+        _ => 1.,
+    }
+}
+
+// the ground is saved as one letter per tile, so a whole chunk fits on one line
+fn get_char_from_ground_tile(tile: GroundTile) -> char {
+    match tile {
+        GroundTile::Grass => 'g',
+        GroundTile::Sand => 's',
+        GroundTile::Water => 'w',
+        GroundTile::Dirt => 'd',
+    }
+}
+
+fn get_ground_tile_from_char(c: char) -> Option<GroundTile> {
+    match c {
+        'g' => Some(GroundTile::Grass),
+        's' => Some(GroundTile::Sand),
+        'w' => Some(GroundTile::Water),
+        'd' => Some(GroundTile::Dirt),
+        _ => None,
+    }
+}
+
+pub fn make_string_rep_of_ground(tiles: &[GroundTile; 256]) -> String {
+    let mut ground_string = String::from("ground ");
+    ground_string.extend(tiles.iter().map(|tile| get_char_from_ground_tile(*tile)));
+    ground_string.push('\n');
+    ground_string
+}
+
+pub fn get_ground_from_string_rep(parts: &[&str]) -> Result<[GroundTile; 256], ParseErrorReason> {
+    let word = parts.get(1).ok_or(ParseErrorReason::TooShort)?;
+    let bad_word = || ParseErrorReason::BadWord(String::from(*word));
+    if word.chars().count() != 256 {
+        return Err(bad_word());
+    }
+    let mut tiles = [GroundTile::Grass; 256];
+    for (i, c) in word.chars().enumerate() {
+        tiles[i] = get_ground_tile_from_char(c).ok_or_else(bad_word)?;
+    }
+    Ok(tiles)
+}
+
+// the ground mostly follows the biome, with sand on the shores of lakes and patches of dirt on
+// dry land
+pub fn generate_ground(seed: u64, pos: (i32, i32)) -> [GroundTile; 256] {
+    let mut tiles = [GroundTile::Grass; 256];
+    for i in 0..256 {
+        let tile = Vec2::new((pos.0 * 16 + i % 16) as f32, (pos.1 * 16 + i / 16) as f32);
+        let dirt = get_fractal_noise(seed, DIRT_PATCH_SALT, tile, 12.);
+        tiles[i as usize] = match get_biome(seed, tile) {
+            Biome::Lake => GroundTile::Water,
+            Biome::Desert => GroundTile::Sand,
+            _ if get_height(seed, tile) < BEACH_HEIGHT => GroundTile::Sand,
+            Biome::RockyHills if dirt > 0.35 => GroundTile::Dirt,
+            Biome::Forest if dirt > 0.6 => GroundTile::Dirt,
+            Biome::Plains if dirt > 0.7 => GroundTile::Dirt,
+            _ => GroundTile::Grass,
+        };
+    }
+    tiles
+}
+
+// one mesh with a coloured square for every tile, so a chunk's ground is drawn all at once
+fn make_ground_mesh(seed: u64, pos: (i32, i32), tiles: &[GroundTile; 256]) -> Mesh {
+    let mut positions = Vec::new();
+    let mut colors = Vec::new();
+    let mut indices = Vec::new();
+    for (i, tile) in tiles.iter().enumerate() {
+        let (x, y) = ((i % 16) as f32, (i / 16) as f32);
+        let shade = get_tile_shade(seed, (pos.0 * 16 + x as i32, pos.1 * 16 + y as i32));
+        let color = get_ground_tile_color(*tile) * shade;
+        let first = positions.len() as u32;
+        for (dx, dy) in [(-0.5, -0.5), (0.5, -0.5), (0.5, 0.5), (-0.5, 0.5)] {
+            positions.push([x + dx, y + dy, 0.]);
+            colors.push(color.as_linear_rgba_f32());
+        }
+        indices.extend([first, first + 1, first + 2, first, first + 2, first + 3]);
+    }
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
+
+// spawns the background of a chunk with its ground drawn on it, and adds the ground to the map
+pub fn spawn_ground(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    ground_map: &mut ResMut<GroundMap>,
+    seed: u64,
+    pos: (i32, i32),
+    tiles: [GroundTile; 256],
+) {
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: meshes.add(make_ground_mesh(seed, pos, &tiles)).into(),
+            material: materials.add(ColorMaterial::default()),
+            transform: Transform::from_xyz((pos.0 * 16) as f32, (pos.1 * 16) as f32, -0.1),
+            ..default()
+        },
+        ChunkBackground {},
+    ));
+    ground_map.chunks.insert(pos, tiles);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn every_kind_of_tile() -> [GroundTile; 256] {
+        let kinds = [
+            GroundTile::Grass,
+            GroundTile::Sand,
+            GroundTile::Water,
+            GroundTile::Dirt,
+        ];
+        let mut tiles = [GroundTile::Grass; 256];
+        for (i, tile) in tiles.iter_mut().enumerate() {
+            *tile = kinds[(i / 3) % 4];
+        }
+        tiles
+    }
+
+    #[test]
+    fn ground_line_round_trips() {
+        let tiles = every_kind_of_tile();
+        let line = make_string_rep_of_ground(&tiles);
+        assert!(line.starts_with("ground gggsss"));
+        let parts = line.trim_end().split(' ').collect::<Vec<&str>>();
+        assert_eq!(get_ground_from_string_rep(&parts), Ok(tiles));
+    }
+
+    #[test]
+    fn bad_ground_lines_are_errors() {
+        assert_eq!(
+            get_ground_from_string_rep(&["ground"]),
+            Err(ParseErrorReason::TooShort)
+        );
+        let short = "g".repeat(255);
+        assert_eq!(
+            get_ground_from_string_rep(&["ground", &short]),
+            Err(ParseErrorReason::BadWord(short.clone()))
+        );
+        let unknown = format!("{}x", short);
+        assert_eq!(
+            get_ground_from_string_rep(&["ground", &unknown]),
+            Err(ParseErrorReason::BadWord(unknown.clone()))
+        );
+    }
+
+    // tiles in chunks below and left of the origin count their place in the chunk from its bottom
+    // left too, so the tile at -1 -1 is the top right of chunk -1 -1
+    #[test]
+    fn tiles_are_found_in_the_right_chunk() {
+        let mut ground_map = GroundMap::default();
+        let mut tiles = [GroundTile::Grass; 256];
+        tiles[0] = GroundTile::Sand;
+        tiles[15] = GroundTile::Dirt;
+        tiles[255] = GroundTile::Water;
+        ground_map.chunks.insert((0, 0), tiles);
+        ground_map.chunks.insert((-1, -1), tiles);
+
+        assert_eq!(ground_map.get_tile(Vec2::ZERO), Some(GroundTile::Sand));
+        assert_eq!(
+            ground_map.get_tile(Vec2::new(15., 0.)),
+            Some(GroundTile::Dirt)
+        );
+        assert_eq!(
+            ground_map.get_tile(Vec2::new(15.4, 15.4)),
+            Some(GroundTile::Water)
+        );
+        assert_eq!(
+            ground_map.get_tile(Vec2::new(-1., -1.)),
+            Some(GroundTile::Water)
+        );
+        assert_eq!(
+            ground_map.get_tile(Vec2::new(-0.6, -0.6)),
+            Some(GroundTile::Water)
+        );
+        assert_eq!(
+            ground_map.get_tile(Vec2::new(-16., -16.)),
+            Some(GroundTile::Sand)
+        );
+        assert_eq!(
+            ground_map.get_tile(Vec2::new(-1., -16.)),
+            Some(GroundTile::Dirt)
+        );
+        // the chunks next to them aren't loaded
+        assert_eq!(ground_map.get_tile(Vec2::new(16., 0.)), None);
+        assert_eq!(ground_map.get_tile(Vec2::new(-1., 0.)), None);
+        assert_eq!(ground_map.get_tile(Vec2::new(-17., -1.)), None);
+    }
+
+    #[test]
+    fn water_slows_things_down() {
+        assert!(
+            get_ground_drag(Some(GroundTile::Water)) > get_ground_drag(Some(GroundTile::Grass))
+        );
+        assert!(get_ground_drag(Some(GroundTile::Water)) > get_ground_drag(None));
+    }
+}
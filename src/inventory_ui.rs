@@ -1,29 +1,140 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, window::PrimaryWindow};
+use std::f32::consts::PI;
 
-use crate::{get_item_color, get_item_texture, AppState, InventorySlot, PlayerInventory};
+use crate::{
+    can_craft, craft, get_item_color, get_item_texture, get_string_rep_from_item_type,
+    insert_block_to_inventory, insert_one_into_chest, make_string_rep_of_recipe, save_players,
+    spawn_dropped_item, unload_all_collectibles, AppState, ChestInventory, InventorySlot, ItemType,
+    Player, PlayerInventory, Recipes, CHEST_SIZE, INVENTORY_SIZE,
+};
 
 #[derive(Component)]
 pub struct InventoryUI;
 
-#[derive(Component, Clone, Copy)]
+#[derive(Component, Clone)]
 pub struct InventoryUISlot {
     pub index: usize,
-    pub item_slot: Option<InventorySlot>,
+    // the selected hotbar slot is drawn bigger, but not in the inventory screen
+    pub hotbar: bool,
+    // the slot is one of the open chest's, instead of the player's
+    pub in_chest: bool,
+}
+
+// the number of items in a slot, drawn in its corner
+#[derive(Component)]
+pub struct InventoryUISlotCount;
+
+// the name of the item under the mouse
+#[derive(Component)]
+pub struct InventoryTooltipUI;
+
+// the name of the selected item, shown above the hotbar for a moment after the selected slot or
+// the item in it changes
+#[derive(Component)]
+pub struct SelectedItemLabelUI {
+    // the selected slot and the item that was in it last frame. None before the first frame, so
+    // the name doesn't pop up as soon as the game starts
+    pub selected: Option<(usize, Option<ItemType>)>,
+    pub time_shown: f32,
+}
+
+// how long the selected item's name stays up, and how long it takes to fade in and out
+const SELECTED_ITEM_LABEL_SECONDS: f32 = 2.;
+const SELECTED_ITEM_LABEL_FADE_IN_SECONDS: f32 = 0.15;
+const SELECTED_ITEM_LABEL_FADE_OUT_SECONDS: f32 = 0.5;
+
+// every slot of the inventory, opened with e. items are moved around by clicking or dragging them
+// with the mouse
+#[derive(Component)]
+pub struct InventoryScreenUI;
+
+// the stack that's been picked up, drawn under the mouse
+#[derive(Component)]
+pub struct HeldStackUI;
+
+#[derive(Component)]
+pub struct HeldStackUICount;
+
+#[derive(Resource, Default)]
+pub struct InventoryScreen {
+    pub open: bool,
+    // the stack picked up with the mouse. it still belongs to where it came from, so it's saved
+    // there, and goes back there when the screen is closed
+    pub held: Option<InventorySlot>,
+    // the slot the held stack was picked up from, as its index and whether it's in the chest, so
+    // letting go of the mouse over a different slot drops it there
+    pub dragged_from: Option<(usize, bool)>,
+    // where the held stack was picked up from, the same way. unlike dragged_from, this lasts until
+    // the hand is empty again, so a stack from the chest can go back there when the screen closes
+    pub held_from: Option<(usize, bool)>,
+    // the chest block that's open next to the inventory. right clicking a chest sets this, which
+    // opens the screen
+    pub chest: Option<Entity>,
+}
+
+// the list of recipes, opened with c. clicking a recipe crafts it once
+#[derive(Component)]
+pub struct CraftingUI;
+
+#[derive(Component)]
+pub struct CraftingUIRecipe {
+    pub index: usize,
+}
+
+#[derive(Component)]
+pub struct CraftingUIMessage;
+
+#[derive(Resource, Default)]
+pub struct CraftingMenu {
+    pub open: bool,
+    // what happened the last time something was crafted
+    pub message: String,
 }
 
 impl Plugin for InventoryUI {
     fn build(&self, app: &mut App) {
+        app.insert_resource(CraftingMenu::default());
+        app.insert_resource(InventoryScreen::default());
         app.add_systems(OnEnter(AppState::Game), spawn_inventory_ui);
-        app.add_systems(OnExit(AppState::Game), despawn_inventory_ui);
+        app.add_systems(
+            OnExit(AppState::Game),
+            (
+                despawn_inventory_ui,
+                close_crafting_ui,
+                close_inventory_screen
+                    .before(save_players)
+                    .before(unload_all_collectibles),
+            ),
+        );
         app.add_systems(
             Update,
-            (update_inventory_ui).run_if(in_state(AppState::Game)),
+            (
+                update_inventory_ui,
+                update_inventory_ui_slot_counts,
+                update_inventory_tooltip_ui,
+                update_selected_item_label_ui,
+                toggle_crafting_ui,
+                crafting_ui_system.after(toggle_crafting_ui),
+                toggle_inventory_screen,
+                inventory_screen_system.after(toggle_inventory_screen),
+                update_held_stack_ui.after(inventory_screen_system),
+            )
+                .run_if(in_state(AppState::Game)),
         );
     }
 }
 
-pub fn spawn_inventory_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
-    build_inventory_ui(&mut commands, &asset_server);
+// clicks on the crafting menu or the inventory screen shouldn't also break blocks or gather things
+// behind them
+pub fn inventory_menus_closed(
+    crafting_menu: Res<CraftingMenu>,
+    inventory_screen: Res<InventoryScreen>,
+) -> bool {
+    !crafting_menu.open && !inventory_screen.open
+}
+
+pub fn spawn_inventory_ui(mut commands: Commands) {
+    build_inventory_ui(&mut commands);
 }
 
 pub fn despawn_inventory_ui(
@@ -35,7 +146,7 @@ pub fn despawn_inventory_ui(
     }
 }
 
-pub fn build_inventory_ui(commands: &mut Commands, asset_server: &Res<AssetServer>) -> Entity {
+pub fn build_inventory_ui(commands: &mut Commands) -> Entity {
     let inventory_ui_entity = commands
         .spawn((
             NodeBundle {
@@ -54,30 +165,130 @@ pub fn build_inventory_ui(commands: &mut Commands, asset_server: &Res<AssetServe
         ))
         .with_children(|parent| {
             for i in 0..9 {
-                parent.spawn((
-                    ButtonBundle {
-                        style: Style {
-                            height: Val::Px(64.),
-                            width: Val::Px(64.),
-                            margin: UiRect::horizontal(Val::Px(10.)),
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                height: Val::Px(64.),
+                                width: Val::Px(64.),
+                                margin: UiRect::horizontal(Val::Px(10.)),
+                                ..default()
+                            },
+                            transform: Transform::default()
+                                .with_rotation(Quat::from_rotation_z(PI)),
+                            background_color: Color::RED.into(),
                             ..default()
                         },
-                        transform: Transform::default()
-                            .with_rotation(Quat::from_rotation_z(3.1415926535)),
-                        background_color: Color::RED.into(),
-                        ..default()
-                    },
-                    InventoryUISlot {
-                        index: i,
-                        item_slot: None,
-                    },
-                ));
+                        InventoryUISlot {
+                            index: i,
+                            hotbar: true,
+                            in_chest: false,
+                        },
+                    ))
+                    .with_children(spawn_inventory_ui_slot_count);
             }
         })
         .id();
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.),
+                    bottom: Val::Percent(16.),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            InventoryUI {},
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 24.,
+                        color: Color::rgba(1., 1., 1., 0.),
+                        ..default()
+                    },
+                ),
+                SelectedItemLabelUI {
+                    selected: None,
+                    time_shown: 0.,
+                },
+            ));
+        });
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 18.,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            ..default()
+        })
+        .with_background_color(Color::rgba(0., 0., 0., 0.7)),
+        InventoryTooltipUI {},
+        InventoryUI {},
+    ));
     inventory_ui_entity
 }
 
+// the slots are turned upside down (so their textures are the right way up), and so is everything
+// in them. the count is turned back over, which also moves it from the top left to the bottom right
+fn spawn_inventory_ui_slot_count(parent: &mut ChildBuilder) {
+    parent.spawn((
+        TextBundle {
+            transform: Transform::default().with_rotation(Quat::from_rotation_z(PI)),
+            ..TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 18.,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(0.),
+                left: Val::Px(3.),
+                ..default()
+            })
+        },
+        InventoryUISlotCount {},
+    ));
+}
+
+// like "directional wire", or "ram (with data)" for items that remember something
+pub fn get_item_display_name(slot: &InventorySlot) -> String {
+    let name = get_string_rep_from_item_type(Some(slot.item_type))
+        .0
+        .replace('_', " ");
+    match slot.data {
+        Some(_) => format!("{} (with data)", name),
+        None => name,
+    }
+}
+
+// what's in the slot a ui slot shows, from either the player's inventory or the open chest
+fn get_ui_slot<'a>(
+    inventory_ui_slot: &InventoryUISlot,
+    inventory: &'a PlayerInventory,
+    chest: Option<&'a ChestInventory>,
+) -> Option<&'a InventorySlot> {
+    let slots = if inventory_ui_slot.in_chest {
+        &chest?.slots
+    } else {
+        &inventory.slots
+    };
+    slots.get(inventory_ui_slot.index)?.as_ref()
+}
+
 fn update_inventory_ui(
     mut inventory_ui_slots: Query<(
         &mut BackgroundColor,
@@ -86,10 +297,15 @@ fn update_inventory_ui(
         &InventoryUISlot,
     )>,
     inventory: Res<PlayerInventory>,
+    inventory_screen: Res<InventoryScreen>,
+    chests: Query<&ChestInventory>,
     asset_server: Res<AssetServer>,
 ) {
-    for (mut color, mut style, mut img, &inventory_ui_slot) in &mut inventory_ui_slots {
-        if let Some(Some(slot)) = inventory.slots.get(inventory_ui_slot.index) {
+    let chest = inventory_screen
+        .chest
+        .and_then(|chest| chests.get(chest).ok());
+    for (mut color, mut style, mut img, inventory_ui_slot) in &mut inventory_ui_slots {
+        if let Some(slot) = get_ui_slot(inventory_ui_slot, &inventory, chest) {
             color.0 = get_item_color(slot.item_type);
             *img = get_item_texture(slot.item_type, &asset_server).into();
         } else {
@@ -97,6 +313,9 @@ fn update_inventory_ui(
             *img = default();
         }
 
+        if !inventory_ui_slot.hotbar {
+            continue;
+        }
         if inventory.selected_slot == inventory_ui_slot.index {
             style.width = Val::Px(80.);
             style.height = Val::Px(80.);
@@ -106,3 +325,843 @@ fn update_inventory_ui(
         }
     }
 }
+
+fn update_inventory_ui_slot_counts(
+    mut inventory_ui_slot_counts: Query<(&mut Text, &Parent), With<InventoryUISlotCount>>,
+    inventory_ui_slots: Query<&InventoryUISlot>,
+    inventory: Res<PlayerInventory>,
+    inventory_screen: Res<InventoryScreen>,
+    chests: Query<&ChestInventory>,
+) {
+    let chest = inventory_screen
+        .chest
+        .and_then(|chest| chests.get(chest).ok());
+    for (mut text, parent) in &mut inventory_ui_slot_counts {
+        let Ok(inventory_ui_slot) = inventory_ui_slots.get(parent.get()) else {
+            continue;
+        };
+        // a single item doesn't need a number on it
+        text.sections[0].value = match get_ui_slot(inventory_ui_slot, &inventory, chest) {
+            Some(slot) if slot.count > 1 => format!("{}", slot.count),
+            _ => String::new(),
+        };
+    }
+}
+
+fn update_inventory_tooltip_ui(
+    mut tooltip_ui: Query<(&mut Text, &mut Style, &mut Visibility), With<InventoryTooltipUI>>,
+    inventory_ui_slots: Query<(&Interaction, &InventoryUISlot)>,
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    inventory: Res<PlayerInventory>,
+    inventory_screen: Res<InventoryScreen>,
+    chests: Query<&ChestInventory>,
+) {
+    let chest = inventory_screen
+        .chest
+        .and_then(|chest| chests.get(chest).ok());
+    let hovered_slot = inventory_ui_slots
+        .iter()
+        .find(|(interaction, _)| **interaction != Interaction::None)
+        .and_then(|(_, inventory_ui_slot)| get_ui_slot(inventory_ui_slot, &inventory, chest));
+    let cursor_position = q_windows.single().cursor_position();
+    for (mut text, mut style, mut visibility) in &mut tooltip_ui {
+        match (hovered_slot, cursor_position) {
+            // the held stack is drawn under the mouse, so the tooltip would be in the way
+            (Some(slot), Some(cursor_position)) if inventory_screen.held.is_none() => {
+                *visibility = Visibility::Visible;
+                text.sections[0].value = get_item_display_name(slot);
+                style.left = Val::Px(cursor_position.x + 12.);
+                style.top = Val::Px(cursor_position.y - 28.);
+            }
+            _ => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+fn update_selected_item_label_ui(
+    mut selected_item_label_ui: Query<(&mut Text, &mut SelectedItemLabelUI)>,
+    inventory: Res<PlayerInventory>,
+    time: Res<Time>,
+) {
+    let slot = inventory
+        .slots
+        .get(inventory.selected_slot)
+        .and_then(|slot| slot.as_ref());
+    let selected = (inventory.selected_slot, slot.map(|slot| slot.item_type));
+    for (mut text, mut label) in &mut selected_item_label_ui {
+        match label.selected {
+            Some(last_selected) if last_selected == selected => {
+                label.time_shown += time.delta_seconds();
+            }
+            last_selected => {
+                text.sections[0].value = slot.map(get_item_display_name).unwrap_or_default();
+                // the first frame only starts keeping track, with the label already faded out
+                label.time_shown = if last_selected.is_some() {
+                    0.
+                } else {
+                    SELECTED_ITEM_LABEL_SECONDS
+                };
+                label.selected = Some(selected);
+            }
+        }
+        let fade_in = label.time_shown / SELECTED_ITEM_LABEL_FADE_IN_SECONDS;
+        let fade_out =
+            (SELECTED_ITEM_LABEL_SECONDS - label.time_shown) / SELECTED_ITEM_LABEL_FADE_OUT_SECONDS;
+        text.sections[0]
+            .style
+            .color
+            .set_a(fade_in.min(fade_out).clamp(0., 1.));
+    }
+}
+
+fn toggle_crafting_ui(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut crafting_menu: ResMut<CraftingMenu>,
+    crafting_ui: Query<Entity, With<CraftingUI>>,
+    recipes: Res<Recipes>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::C) {
+        return;
+    }
+    crafting_menu.open = !crafting_menu.open;
+    crafting_menu.message.clear();
+    if crafting_menu.open {
+        build_crafting_ui(&mut commands, &recipes);
+    } else {
+        for entity in crafting_ui.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+pub fn close_crafting_ui(
+    mut commands: Commands,
+    mut crafting_menu: ResMut<CraftingMenu>,
+    crafting_ui: Query<Entity, With<CraftingUI>>,
+) {
+    crafting_menu.open = false;
+    for entity in crafting_ui.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub fn build_crafting_ui(commands: &mut Commands, recipes: &Recipes) -> Entity {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(10.),
+                    right: Val::Px(10.),
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(8.)),
+                    ..default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.6).into(),
+                ..default()
+            },
+            CraftingUI {},
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Crafting (c to close)",
+                TextStyle {
+                    font_size: 20.,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+            for (i, recipe) in recipes.0.iter().enumerate() {
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                padding: UiRect::all(Val::Px(3.)),
+                                margin: UiRect::top(Val::Px(2.)),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                        CraftingUIRecipe { index: i },
+                    ))
+                    .with_children(|button| {
+                        button.spawn(TextBundle::from_section(
+                            make_string_rep_of_recipe(recipe),
+                            TextStyle {
+                                font_size: 16.,
+                                color: Color::WHITE,
+                                ..default()
+                            },
+                        ));
+                    });
+            }
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 16.,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+                CraftingUIMessage {},
+            ));
+        })
+        .id()
+}
+
+// crafts the recipe that was clicked, and greys out the ones there isn't enough for
+fn crafting_ui_system(
+    mut recipe_buttons: Query<(Ref<Interaction>, &mut BackgroundColor, &CraftingUIRecipe)>,
+    mut message_text: Query<&mut Text, With<CraftingUIMessage>>,
+    mut crafting_menu: ResMut<CraftingMenu>,
+    mut inventory: ResMut<PlayerInventory>,
+    recipes: Res<Recipes>,
+) {
+    for (interaction, mut color, recipe_button) in &mut recipe_buttons {
+        let recipe = &recipes.0[recipe_button.index];
+        // only once per click, not every frame the button is held
+        if *interaction == Interaction::Pressed && interaction.is_changed() {
+            crafting_menu.message = match craft(&mut inventory, recipe) {
+                Ok(_) => format!("made {}", make_string_rep_of_recipe(recipe)),
+                Err(error) => error,
+            };
+        }
+        color.0 = match (*interaction, can_craft(&inventory, recipe)) {
+            (_, false) => Color::rgba(0.3, 0.3, 0.3, 0.8),
+            (Interaction::None, true) => Color::rgba(0.2, 0.5, 0.2, 0.8),
+            (_, true) => Color::rgba(0.3, 0.7, 0.3, 0.8),
+        };
+    }
+    for mut text in &mut message_text {
+        text.sections[0].value = crafting_menu.message.clone();
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn toggle_inventory_screen(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut inventory_screen: ResMut<InventoryScreen>,
+    mut inventory: ResMut<PlayerInventory>,
+    inventory_screen_ui: Query<Entity, Or<(With<InventoryScreenUI>, With<HeldStackUI>)>>,
+    mut chests: Query<&mut ChestInventory>,
+    player: Query<&Transform, With<Player>>,
+    asset_server: Res<AssetServer>,
+) {
+    let chest_opened = !inventory_screen.open && inventory_screen.chest.is_some();
+    // the chest could have been unloaded while it was open
+    let chest_gone = inventory_screen
+        .chest
+        .is_some_and(|chest| chests.get(chest).is_err());
+    if inventory_screen.open && (keyboard_input.just_pressed(KeyCode::E) || chest_gone) {
+        let chest = inventory_screen
+            .chest
+            .and_then(|chest| chests.get_mut(chest).ok());
+        let left_over = put_back_held_stack(
+            &mut inventory_screen,
+            &mut inventory,
+            chest.map(Mut::into_inner),
+        );
+        if let Some(left_over) = left_over {
+            drop_at_player(&mut commands, &asset_server, &player, left_over);
+        }
+        inventory_screen.open = false;
+        inventory_screen.chest = None;
+        for entity in inventory_screen_ui.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+    } else if keyboard_input.just_pressed(KeyCode::E) || chest_opened {
+        inventory_screen.open = true;
+        build_inventory_screen(&mut commands, inventory_screen.chest.is_some());
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn close_inventory_screen(
+    mut commands: Commands,
+    mut inventory_screen: ResMut<InventoryScreen>,
+    mut inventory: ResMut<PlayerInventory>,
+    inventory_screen_ui: Query<Entity, Or<(With<InventoryScreenUI>, With<HeldStackUI>)>>,
+    mut chests: Query<&mut ChestInventory>,
+    player: Query<&Transform, With<Player>>,
+    asset_server: Res<AssetServer>,
+) {
+    let chest = inventory_screen
+        .chest
+        .and_then(|chest| chests.get_mut(chest).ok());
+    let left_over = put_back_held_stack(
+        &mut inventory_screen,
+        &mut inventory,
+        chest.map(Mut::into_inner),
+    );
+    if let Some(left_over) = left_over {
+        drop_at_player(&mut commands, &asset_server, &player, left_over);
+    }
+    inventory_screen.open = false;
+    inventory_screen.chest = None;
+    for entity in inventory_screen_ui.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// puts the held stack back in the inventory. a stack from the inventory fits, since the slot it
+// came from was kept for it, unless the player put something else there. what doesn't fit goes
+// back in the open chest if it came from there. returns whatever is left over after that
+fn put_back_held_stack(
+    inventory_screen: &mut InventoryScreen,
+    inventory: &mut PlayerInventory,
+    chest: Option<&mut ChestInventory>,
+) -> Option<InventorySlot> {
+    inventory_screen.dragged_from = None;
+    inventory.held_slot = None;
+    let held_from = inventory_screen.held_from.take();
+    let mut held = inventory_screen.held.take()?;
+    held.count =
+        insert_block_to_inventory(inventory, held.item_type, held.count, held.data.clone());
+    if let (Some((_, true)), Some(chest)) = (held_from, chest) {
+        while held.count > 0 && insert_one_into_chest(chest, &held) {
+            held.count -= 1;
+        }
+    }
+    (held.count > 0).then_some(held)
+}
+
+// the inventory's slots as they're saved. a held stack from the inventory is saved back in it, so
+// it isn't lost if the game stops before it's put down
+pub fn get_inventory_slots_to_save(
+    inventory: &PlayerInventory,
+    inventory_screen: &InventoryScreen,
+) -> Vec<Option<InventorySlot>> {
+    let mut inventory = inventory.clone();
+    if let (Some(held), Some((_, false))) = (&inventory_screen.held, inventory_screen.held_from) {
+        inventory.held_slot = None;
+        insert_block_to_inventory(
+            &mut inventory,
+            held.item_type,
+            held.count,
+            held.data.clone(),
+        );
+    }
+    inventory.slots
+}
+
+// a chest as it's saved. a held stack from this chest is saved back in it, the same way
+pub fn get_chest_to_save(
+    chest_entity: Entity,
+    chest: &ChestInventory,
+    inventory_screen: &InventoryScreen,
+) -> ChestInventory {
+    let mut chest = chest.clone();
+    if let (Some(held), Some((_, true))) = (&inventory_screen.held, inventory_screen.held_from) {
+        if inventory_screen.chest == Some(chest_entity) {
+            for _ in 0..held.count {
+                insert_one_into_chest(&mut chest, held);
+            }
+        }
+    }
+    chest
+}
+
+// a held stack with nowhere to go back to is dropped where the player is, instead of being lost
+fn drop_at_player(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    player: &Query<&Transform, With<Player>>,
+    slot: InventorySlot,
+) {
+    let player_pos = player
+        .get_single()
+        .map_or(Vec2::ZERO, |transform| transform.translation.truncate());
+    spawn_dropped_item(commands, asset_server, player_pos, slot, 0.);
+}
+
+// the storage slots in rows of 9, with the hotbar under them. if a chest is open, its slots are
+// shown next to them
+pub fn build_inventory_screen(commands: &mut Commands, chest_open: bool) -> Entity {
+    let mut rows = (9..INVENTORY_SIZE)
+        .step_by(9)
+        .map(|start| start..start + 9)
+        .collect::<Vec<_>>();
+    rows.push(0..9);
+    let chest_rows = (0..CHEST_SIZE)
+        .step_by(9)
+        .map(|start| start..start + 9)
+        .collect::<Vec<_>>();
+    let inventory_screen_entity = commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(85.),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    column_gap: Val::Px(16.),
+                    ..default()
+                },
+                ..default()
+            },
+            InventoryScreenUI {},
+        ))
+        .with_children(|parent| {
+            if chest_open {
+                spawn_inventory_screen_panel(parent, chest_rows, true);
+            }
+            spawn_inventory_screen_panel(parent, rows, false);
+        })
+        .id();
+    commands
+        .spawn((
+            ImageBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Px(40.),
+                    height: Val::Px(40.),
+                    ..default()
+                },
+                transform: Transform::default().with_rotation(Quat::from_rotation_z(PI)),
+                visibility: Visibility::Hidden,
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            HeldStackUI {},
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 16.,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                )
+                .with_style(Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(0.),
+                    right: Val::Px(2.),
+                    ..default()
+                }),
+                HeldStackUICount {},
+            ));
+        });
+    inventory_screen_entity
+}
+
+fn spawn_inventory_screen_panel(
+    parent: &mut ChildBuilder,
+    rows: Vec<std::ops::Range<usize>>,
+    in_chest: bool,
+) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(8.)),
+                ..default()
+            },
+            background_color: Color::rgba(0., 0., 0., 0.6).into(),
+            ..default()
+        })
+        .with_children(|panel| {
+            for (row_number, row) in rows.into_iter().enumerate() {
+                // the hotbar is spaced out a little from the rest of the inventory
+                let gap = if !in_chest && row.start == 0 && row_number > 0 {
+                    16.
+                } else {
+                    0.
+                };
+                panel
+                    .spawn(NodeBundle {
+                        style: Style {
+                            margin: UiRect::top(Val::Px(gap)),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|row_node| {
+                        for i in row {
+                            row_node
+                                .spawn((
+                                    ButtonBundle {
+                                        style: Style {
+                                            height: Val::Px(48.),
+                                            width: Val::Px(48.),
+                                            margin: UiRect::all(Val::Px(4.)),
+                                            ..default()
+                                        },
+                                        transform: Transform::default()
+                                            .with_rotation(Quat::from_rotation_z(PI)),
+                                        ..default()
+                                    },
+                                    InventoryUISlot {
+                                        index: i,
+                                        hotbar: false,
+                                        in_chest,
+                                    },
+                                ))
+                                .with_children(spawn_inventory_ui_slot_count);
+                        }
+                    });
+            }
+        });
+}
+
+// left clicking a slot picks up its stack, or puts down the held one (adding to the stack there if
+// it's the same item, or swapping them if it isn't). right clicking picks up half a stack, or puts
+// down one of the held items. letting go of the mouse over another slot drops the held stack there,
+// so stacks can be dragged too
+fn inventory_screen_system(
+    inventory_ui_slots: Query<(&Interaction, &InventoryUISlot)>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    mut inventory_screen: ResMut<InventoryScreen>,
+    mut inventory: ResMut<PlayerInventory>,
+    mut chests: Query<&mut ChestInventory>,
+) {
+    if !inventory_screen.open {
+        return;
+    }
+    let hovered = inventory_ui_slots
+        .iter()
+        .find(|(interaction, _)| **interaction != Interaction::None)
+        .map(|(_, inventory_ui_slot)| (inventory_ui_slot.index, inventory_ui_slot.in_chest));
+    let mut chest = inventory_screen
+        .chest
+        .and_then(|chest| chests.get_mut(chest).ok());
+    let slot = match hovered {
+        Some((index, true)) => chest.as_mut().and_then(|chest| chest.slots.get_mut(index)),
+        Some((index, false)) => inventory.slots.get_mut(index),
+        None => None,
+    };
+    let Some(slot) = slot else {
+        if mouse_buttons.just_released(MouseButton::Left) {
+            inventory_screen.dragged_from = None;
+        }
+        return;
+    };
+    let inventory_screen = &mut *inventory_screen;
+    let was_holding = inventory_screen.held.is_some();
+    if mouse_buttons.just_pressed(MouseButton::Left) {
+        click_slot(slot, &mut inventory_screen.held);
+        inventory_screen.dragged_from = if was_holding { None } else { hovered };
+    } else if mouse_buttons.just_released(MouseButton::Left) {
+        if inventory_screen.dragged_from.is_some() && inventory_screen.dragged_from != hovered {
+            click_slot(slot, &mut inventory_screen.held);
+        }
+        inventory_screen.dragged_from = None;
+    } else if mouse_buttons.just_pressed(MouseButton::Right) {
+        right_click_slot(slot, &mut inventory_screen.held);
+    }
+    inventory_screen.held_from = get_held_from(
+        inventory_screen.held_from,
+        was_holding,
+        inventory_screen.held.is_some(),
+        hovered,
+    );
+    // only inventory slots are kept for the held stack. stacks from a chest go back to the chest
+    inventory.held_slot = match inventory_screen.held_from {
+        Some((index, false)) => Some(index),
+        _ => None,
+    };
+}
+
+// where the held stack came from. it's the slot a stack is picked up from, until the hand is empty
+// again, even if the held stack is swapped for others in the meantime
+fn get_held_from(
+    held_from: Option<(usize, bool)>,
+    was_holding: bool,
+    holding: bool,
+    clicked: Option<(usize, bool)>,
+) -> Option<(usize, bool)> {
+    match (was_holding, holding) {
+        (_, false) => None,
+        (false, true) => clicked,
+        (true, true) => held_from,
+    }
+}
+
+fn click_slot(slot: &mut Option<InventorySlot>, held: &mut Option<InventorySlot>) {
+    match (slot.as_mut(), held.as_mut()) {
+        (Some(slot_stack), Some(held_stack))
+            if slot_stack.can_stack_with(held_stack.item_type, &held_stack.data) =>
+        {
+            let added = held_stack
+                .count
+                .min(slot_stack.max_stack.saturating_sub(slot_stack.count));
+            slot_stack.count += added;
+            held_stack.count -= added;
+            if held_stack.count == 0 {
+                *held = None;
+            }
+        }
+        _ => std::mem::swap(slot, held),
+    }
+}
+
+fn right_click_slot(slot: &mut Option<InventorySlot>, held: &mut Option<InventorySlot>) {
+    match (slot.as_mut(), held.as_mut()) {
+        (Some(slot_stack), None) => {
+            let taken = slot_stack.count.div_ceil(2);
+            slot_stack.count -= taken;
+            *held = Some(InventorySlot {
+                count: taken,
+                ..slot_stack.clone()
+            });
+            if slot_stack.count == 0 {
+                *slot = None;
+            }
+        }
+        (None, Some(held_stack)) => {
+            held_stack.count -= 1;
+            *slot = Some(InventorySlot {
+                count: 1,
+                ..held_stack.clone()
+            });
+            if held_stack.count == 0 {
+                *held = None;
+            }
+        }
+        (Some(slot_stack), Some(held_stack)) => {
+            if slot_stack.can_stack_with(held_stack.item_type, &held_stack.data)
+                && slot_stack.count < slot_stack.max_stack
+            {
+                slot_stack.count += 1;
+                held_stack.count -= 1;
+                if held_stack.count == 0 {
+                    *held = None;
+                }
+            }
+        }
+        (None, None) => {}
+    }
+}
+
+fn update_held_stack_ui(
+    mut held_stack_ui: Query<
+        (
+            &mut Style,
+            &mut Visibility,
+            &mut BackgroundColor,
+            &mut UiImage,
+        ),
+        With<HeldStackUI>,
+    >,
+    mut held_stack_ui_count: Query<&mut Text, With<HeldStackUICount>>,
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    inventory_screen: Res<InventoryScreen>,
+    asset_server: Res<AssetServer>,
+) {
+    let cursor_position = q_windows.single().cursor_position();
+    for (mut style, mut visibility, mut color, mut img) in &mut held_stack_ui {
+        match (&inventory_screen.held, cursor_position) {
+            (Some(held), Some(cursor_position)) => {
+                *visibility = Visibility::Visible;
+                style.left = Val::Px(cursor_position.x - 20.);
+                style.top = Val::Px(cursor_position.y - 20.);
+                color.0 = get_item_color(held.item_type);
+                *img = get_item_texture(held.item_type, &asset_server).into();
+            }
+            _ => *visibility = Visibility::Hidden,
+        }
+    }
+    for mut text in &mut held_stack_ui_count {
+        text.sections[0].value = match &inventory_screen.held {
+            Some(held) if held.count > 1 => format!("{}", held.count),
+            _ => String::new(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stack(item_type: ItemType, count: usize) -> Option<InventorySlot> {
+        Some(InventorySlot::new(item_type, count, None))
+    }
+
+    fn full_inventory() -> PlayerInventory {
+        PlayerInventory {
+            selected_slot: 0,
+            slots: vec![stack(ItemType::Stone(0), 64); INVENTORY_SIZE],
+            held_slot: None,
+        }
+    }
+
+    // while a stack is held, picking things up can't fill the slot it came from, so it can always
+    // be put back
+    #[test]
+    fn slot_the_held_stack_came_from_is_kept_for_it() {
+        let mut inventory = full_inventory();
+        let mut inventory_screen = InventoryScreen::default();
+        click_slot(&mut inventory.slots[4], &mut inventory_screen.held);
+        inventory_screen.held_from = get_held_from(None, false, true, Some((4, false)));
+        inventory.held_slot = Some(4);
+
+        assert_eq!(
+            insert_block_to_inventory(&mut inventory, ItemType::Wood, 10, None),
+            10
+        );
+        assert!(!inventory.has_room_for(ItemType::Wood, &None));
+        assert_eq!(inventory.slots[4], None);
+
+        assert_eq!(
+            put_back_held_stack(&mut inventory_screen, &mut inventory, None),
+            None
+        );
+        assert_eq!(inventory_screen.held, None);
+        assert_eq!(inventory.held_slot, None);
+        assert_eq!(inventory.slots[4], stack(ItemType::Stone(0), 64));
+    }
+
+    #[test]
+    fn held_stack_remembers_where_it_came_from_until_the_hand_is_empty() {
+        assert_eq!(
+            get_held_from(None, false, true, Some((3, true))),
+            Some((3, true))
+        );
+        // swapping the held stack with another one keeps where the first one came from
+        assert_eq!(
+            get_held_from(Some((3, true)), true, true, Some((5, false))),
+            Some((3, true))
+        );
+        assert_eq!(
+            get_held_from(Some((3, true)), true, false, Some((5, false))),
+            None
+        );
+    }
+
+    // with a full inventory, a stack taken out of a chest goes back in the chest when the screen is
+    // closed. if the chest is gone or full, it's left over to be dropped at the player
+    #[test]
+    fn held_stack_goes_back_to_its_chest_when_the_inventory_is_full() {
+        let mut inventory = full_inventory();
+        let mut chest = ChestInventory::default();
+        chest.slots[2] = stack(ItemType::Wood, 10);
+        let mut inventory_screen = InventoryScreen::default();
+        click_slot(&mut chest.slots[2], &mut inventory_screen.held);
+        inventory_screen.held_from = Some((2, true));
+
+        assert_eq!(
+            put_back_held_stack(&mut inventory_screen, &mut inventory, Some(&mut chest)),
+            None
+        );
+        assert_eq!(inventory_screen.held, None);
+        assert_eq!(inventory.count(ItemType::Wood), 0);
+        assert_eq!(chest.slots[0], stack(ItemType::Wood, 10));
+
+        // the chest was unloaded while the stack was held
+        click_slot(&mut chest.slots[0], &mut inventory_screen.held);
+        inventory_screen.held_from = Some((0, true));
+        assert_eq!(
+            put_back_held_stack(&mut inventory_screen, &mut inventory, None),
+            stack(ItemType::Wood, 10)
+        );
+
+        // the chest was filled up while the stack was held
+        let mut full_chest = ChestInventory {
+            slots: vec![stack(ItemType::Stone(0), 64); CHEST_SIZE],
+        };
+        inventory_screen.held = stack(ItemType::Wood, 7);
+        inventory_screen.held_from = Some((1, true));
+        assert_eq!(
+            put_back_held_stack(&mut inventory_screen, &mut inventory, Some(&mut full_chest)),
+            stack(ItemType::Wood, 7)
+        );
+        assert_eq!(inventory_screen.held, None);
+    }
+
+    #[test]
+    fn clicking_merges_the_held_stack_up_to_the_max() {
+        let mut slot = stack(ItemType::Wood, 60);
+        let mut held = stack(ItemType::Wood, 10);
+        click_slot(&mut slot, &mut held);
+        assert_eq!(slot, stack(ItemType::Wood, 64));
+        assert_eq!(held, stack(ItemType::Wood, 6));
+
+        // nothing fits on a full stack, so the held items stay held
+        click_slot(&mut slot, &mut held);
+        assert_eq!(slot, stack(ItemType::Wood, 64));
+        assert_eq!(held, stack(ItemType::Wood, 6));
+
+        // stacks that can't merge are swapped
+        let mut held = stack(ItemType::Stone(0), 3);
+        click_slot(&mut slot, &mut held);
+        assert_eq!(slot, stack(ItemType::Stone(0), 3));
+        assert_eq!(held, stack(ItemType::Wood, 64));
+    }
+
+    #[test]
+    fn right_clicking_takes_half_rounded_up() {
+        let mut slot = stack(ItemType::Wood, 7);
+        let mut held = None;
+        right_click_slot(&mut slot, &mut held);
+        assert_eq!(slot, stack(ItemType::Wood, 3));
+        assert_eq!(held, stack(ItemType::Wood, 4));
+
+        let mut slot = stack(ItemType::Wood, 1);
+        let mut held = None;
+        right_click_slot(&mut slot, &mut held);
+        assert_eq!(slot, None);
+        assert_eq!(held, stack(ItemType::Wood, 1));
+    }
+
+    #[test]
+    fn right_clicking_with_a_held_stack_places_one() {
+        let mut slot = None;
+        let mut held = stack(ItemType::Wood, 5);
+        right_click_slot(&mut slot, &mut held);
+        assert_eq!(slot, stack(ItemType::Wood, 1));
+        assert_eq!(held, stack(ItemType::Wood, 4));
+
+        right_click_slot(&mut slot, &mut held);
+        assert_eq!(slot, stack(ItemType::Wood, 2));
+        assert_eq!(held, stack(ItemType::Wood, 3));
+
+        // the last item leaves the hand empty
+        let mut slot = None;
+        let mut held = stack(ItemType::Wood, 1);
+        right_click_slot(&mut slot, &mut held);
+        assert_eq!(slot, stack(ItemType::Wood, 1));
+        assert_eq!(held, None);
+    }
+
+    // while a stack is held, it's saved back where it came from, and only there
+    #[test]
+    fn held_stack_is_saved_where_it_came_from() {
+        let mut inventory = full_inventory();
+        let mut inventory_screen = InventoryScreen::default();
+        click_slot(&mut inventory.slots[4], &mut inventory_screen.held);
+        inventory_screen.held_from = Some((4, false));
+        inventory.held_slot = Some(4);
+        let chest_entity = Entity::from_raw(7);
+        inventory_screen.chest = Some(chest_entity);
+        let chest = ChestInventory::default();
+
+        let slots = get_inventory_slots_to_save(&inventory, &inventory_screen);
+        assert_eq!(slots[4], stack(ItemType::Stone(0), 64));
+        assert_eq!(
+            get_chest_to_save(chest_entity, &chest, &inventory_screen).slots,
+            chest.slots
+        );
+        // saving doesn't put the stack down
+        assert_eq!(inventory.slots[4], None);
+        assert_eq!(inventory_screen.held, stack(ItemType::Stone(0), 64));
+
+        inventory_screen.held = stack(ItemType::Wood, 3);
+        inventory_screen.held_from = Some((0, true));
+        inventory.held_slot = None;
+        let slots = get_inventory_slots_to_save(&inventory, &inventory_screen);
+        assert_eq!(slots, inventory.slots);
+        assert_eq!(
+            get_chest_to_save(chest_entity, &chest, &inventory_screen).slots[0],
+            stack(ItemType::Wood, 3)
+        );
+        // another chest doesn't get it
+        assert_eq!(
+            get_chest_to_save(Entity::from_raw(8), &chest, &inventory_screen).slots,
+            chest.slots
+        );
+    }
+}
@@ -0,0 +1,7 @@
+// the parts of the game that don't need bevy, so they can be tested and benchmarked on their own
+// without starting the game. main.rs uses them from here
+pub mod block_type;
+pub mod circuit;
+
+pub use block_type::*;
+pub use circuit::*;
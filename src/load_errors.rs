@@ -0,0 +1,300 @@
+use bevy::prelude::*;
+
+use std::{collections::HashSet, fmt, fs, str::FromStr};
+
+use crate::AppState;
+
+// how long the summary of what couldn't be loaded stays on screen, in seconds
+const LOAD_ERRORS_UI_SECONDS: f32 = 10.;
+// the most errors listed in the summary, the rest are only counted
+const LOAD_ERRORS_UI_MAX_LINES: usize = 5;
+
+// why a line in a save file couldn't be read
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseErrorReason {
+    // the line ends before all the words it needs
+    TooShort,
+    // a word isn't the kind of number it should be
+    BadWord(String),
+    // the line isn't a block, item or entity the game knows about
+    UnknownType(String),
+    // a binary file is damaged, so none of it could be read
+    Damaged(String),
+    // a chunk file's header doesn't match what's in the file, so some of it may be missing
+    HeaderMismatch(String),
+}
+
+impl fmt::Display for ParseErrorReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseErrorReason::TooShort => write!(f, "the line ends too early"),
+            ParseErrorReason::BadWord(word) => write!(f, "couldn't read \"{}\"", word),
+            ParseErrorReason::UnknownType(name) => write!(f, "unknown type \"{}\"", name),
+            ParseErrorReason::Damaged(error) => write!(f, "damaged ({})", error),
+            ParseErrorReason::HeaderMismatch(mismatch) => {
+                write!(f, "the header doesn't match the file ({})", mismatch)
+            }
+        }
+    }
+}
+
+// a line in a save file that couldn't be read. line is 0 if the whole file couldn't be read
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    pub file: String,
+    pub line: usize,
+    pub reason: ParseErrorReason,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}: {}", self.file, self.reason)
+        } else {
+            write!(f, "{} line {}: {}", self.file, self.line, self.reason)
+        }
+    }
+}
+
+// parses the word at index i of the words in a string representation
+pub fn parse_word<T: FromStr>(parts: &[&str], i: usize) -> Result<T, ParseErrorReason> {
+    let word = parts.get(i).ok_or(ParseErrorReason::TooShort)?;
+    word.parse::<T>()
+        .map_err(|_| ParseErrorReason::BadWord(String::from(*word)))
+}
+
+// like parse_word, but gives back default if there is no word at index i, since things saved by
+// older versions of the game may not have all their data
+pub fn parse_word_or<T: FromStr>(
+    parts: &[&str],
+    i: usize,
+    default: T,
+) -> Result<T, ParseErrorReason> {
+    if parts.len() > i {
+        parse_word(parts, i)
+    } else {
+        Ok(default)
+    }
+}
+
+// everything that couldn't be loaded since the game was started
+#[derive(Resource, Default)]
+pub struct LoadErrors {
+    pub errors: Vec<ParseError>,
+    // files that were too damaged to read at all, so they're only backed up and reported once
+    damaged_files: HashSet<String>,
+    // how many errors there were when the summary was last shown, so only new ones are shown
+    shown: usize,
+    hide_timer: Timer,
+}
+
+impl LoadErrors {
+    // records the lines of a file that couldn't be read. the bad lines are skipped, so they would be
+    // lost the next time the file is saved. so if there are any, a copy of the file is written next
+    // to it with .corrupt added to its name
+    pub fn add_file_errors(&mut self, file: &str, text: &str, errors: Vec<ParseError>) {
+        self.add_errors_with_backup(file, &format!("{}.corrupt", file), text, errors);
+    }
+
+    // like add_file_errors, but for a chunk in a region file. only that chunk's lines are backed up,
+    // next to the region with the chunk's index in the name
+    pub fn add_region_chunk_errors(
+        &mut self,
+        region_file: &str,
+        index: usize,
+        text: &str,
+        errors: Vec<ParseError>,
+    ) {
+        let backup = format!("{}.chunk_{}.corrupt", region_file, index);
+        let name = get_region_chunk_name(region_file, index);
+        self.add_errors_with_backup(&name, &backup, text, errors);
+    }
+
+    fn add_errors_with_backup(
+        &mut self,
+        name: &str,
+        backup: &str,
+        text: &str,
+        errors: Vec<ParseError>,
+    ) {
+        if errors.is_empty() {
+            return;
+        }
+        for error in &errors {
+            println!("skipped {}", error);
+        }
+        if fs::write(backup, text).is_ok() {
+            println!("saved a backup of {} to {}", name, backup);
+        } else {
+            println!("error saving a backup of {}", name);
+        }
+        self.errors.extend(errors);
+    }
+
+    // records a binary file that's too damaged to read, and backs it up with .corrupt added to
+    // its name
+    pub fn add_damaged_file(&mut self, file: &str, error: String) {
+        if !self.damaged_files.insert(String::from(file)) {
+            return;
+        }
+        let error = ParseError {
+            file: String::from(file),
+            line: 0,
+            reason: ParseErrorReason::Damaged(error),
+        };
+        println!("skipped {}", error);
+        backup_damaged_file(file);
+        self.errors.push(error);
+    }
+}
+
+// what a chunk in a region file is called in errors, since it doesn't have a file of its own
+pub fn get_region_chunk_name(region_file: &str, index: usize) -> String {
+    format!("{} chunk {}", region_file, index)
+}
+
+pub fn backup_damaged_file(file: &str) {
+    let backup = format!("{}.corrupt", file);
+    if fs::copy(file, &backup).is_ok() {
+        println!("saved a backup of {} to {}", file, backup);
+    } else {
+        println!("error saving a backup of {}", file);
+    }
+}
+
+#[derive(Component)]
+pub struct LoadErrorsUI;
+
+impl Plugin for LoadErrorsUI {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(LoadErrors::default());
+        app.add_systems(OnExit(AppState::Game), despawn_load_errors_ui);
+        app.add_systems(
+            Update,
+            (update_load_errors_ui).run_if(in_state(AppState::Game)),
+        );
+    }
+}
+
+pub fn despawn_load_errors_ui(
+    load_errors_ui: Query<Entity, With<LoadErrorsUI>>,
+    mut commands: Commands,
+) {
+    for entity in load_errors_ui.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// shows a summary of the new errors in the corner of the screen for a while
+fn update_load_errors_ui(
+    mut commands: Commands,
+    mut load_errors: ResMut<LoadErrors>,
+    load_errors_ui: Query<Entity, With<LoadErrorsUI>>,
+    time: Res<Time>,
+) {
+    if load_errors.errors.len() > load_errors.shown {
+        for entity in load_errors_ui.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        build_load_errors_ui(&mut commands, &load_errors.errors[load_errors.shown..]);
+        load_errors.shown = load_errors.errors.len();
+        load_errors.hide_timer = Timer::from_seconds(LOAD_ERRORS_UI_SECONDS, TimerMode::Once);
+    } else if load_errors.hide_timer.tick(time.delta()).just_finished() {
+        for entity in load_errors_ui.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+pub fn build_load_errors_ui(commands: &mut Commands, errors: &[ParseError]) -> Entity {
+    let mut text = format!(
+        "Some saved data couldn't be loaded, so {} bad lines were skipped. The files they were in were backed up with .corrupt on the end.",
+        errors.len()
+    );
+    for error in errors.iter().take(LOAD_ERRORS_UI_MAX_LINES) {
+        text.push_str(format!("\n{}", error).as_str());
+    }
+    if errors.len() > LOAD_ERRORS_UI_MAX_LINES {
+        text.push_str(
+            format!("\n...and {} more", errors.len() - LOAD_ERRORS_UI_MAX_LINES).as_str(),
+        );
+    }
+
+    commands
+        .spawn((
+            TextBundle::from_section(
+                text,
+                TextStyle {
+                    font_size: 18.,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.),
+                left: Val::Px(10.),
+                max_width: Val::Percent(60.),
+                padding: UiRect::all(Val::Px(8.)),
+                ..default()
+            })
+            .with_background_color(Color::rgba(0., 0., 0., 0.6)),
+            LoadErrorsUI {},
+        ))
+        .id()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn words_that_are_missing_or_cant_be_read_are_errors() {
+        let parts = ["slot", "wood", "five"];
+        assert_eq!(parse_word::<String>(&parts, 1), Ok(String::from("wood")));
+        assert_eq!(
+            parse_word::<usize>(&parts, 2),
+            Err(ParseErrorReason::BadWord(String::from("five")))
+        );
+        assert_eq!(
+            parse_word::<usize>(&parts, 3),
+            Err(ParseErrorReason::TooShort)
+        );
+        // only a missing word gets the default, not one that can't be read
+        assert_eq!(parse_word_or::<usize>(&parts, 3, 7), Ok(7));
+        assert_eq!(
+            parse_word_or::<usize>(&parts, 2, 7),
+            Err(ParseErrorReason::BadWord(String::from("five")))
+        );
+    }
+
+    // a file is only backed up if some of it couldn't be read, and the backup is the whole file
+    #[test]
+    fn file_with_bad_lines_is_backed_up() {
+        let dir =
+            std::env::temp_dir().join(format!("bevy_game_4_load_errors_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("players.txt").to_string_lossy().to_string();
+        let backup = format!("{}.corrupt", file);
+        let text = "slot wood 5\nslot wood five\n";
+        let mut load_errors = LoadErrors::default();
+
+        load_errors.add_file_errors(&file, text, vec![]);
+        assert!(fs::metadata(&backup).is_err());
+        assert!(load_errors.errors.is_empty());
+
+        let error = ParseError {
+            file: file.clone(),
+            line: 2,
+            reason: ParseErrorReason::BadWord(String::from("five")),
+        };
+        load_errors.add_file_errors(&file, text, vec![error]);
+        assert_eq!(fs::read_to_string(&backup).unwrap(), text);
+        assert_eq!(load_errors.errors.len(), 1);
+        assert_eq!(
+            load_errors.errors[0].to_string(),
+            format!("{} line 2: couldn't read \"five\"", file)
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
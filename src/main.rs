@@ -2,23 +2,50 @@ use bevy::{
     core_pipeline::bloom::BloomSettings, prelude::*, render::camera::ScalingMode,
     time::common_conditions::on_timer, window::PrimaryWindow,
 };
+use bevy_game_4::{block_type::*, circuit::*};
 use std::{
-    collections::{HashMap, HashSet, VecDeque},
+    collections::{HashMap, HashSet},
     time::Duration,
 };
 mod block;
+mod boulder;
+mod cactus;
+mod chest;
 mod chunk_loader_unloader;
+mod conveyor;
+mod crafting;
+mod dropped_item;
+mod ground;
 mod inventory_ui;
+mod load_errors;
+mod ore;
 mod player;
+mod region;
 mod rock;
 mod tree;
+mod world;
+mod world_generation;
+mod world_menu;
 
 use block::*;
+use boulder::*;
+use cactus::*;
+use chest::*;
 use chunk_loader_unloader::*;
+use conveyor::*;
+use crafting::*;
+use dropped_item::*;
+use ground::*;
 use inventory_ui::*;
+use load_errors::*;
+use ore::*;
 use player::*;
+use region::*;
 use rock::*;
 use tree::*;
+use world::*;
+use world_generation::*;
+use world_menu::*;
 
 #[derive(States, Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
 pub enum AppState {
@@ -65,10 +92,18 @@ pub struct Collectible {
 const PLAYER_ACCELERATION: f32 = 80.;
 const DRAG: f32 = 100.;
 const MAP_SIZE: f32 = 2000.;
+// the rate at which circuits are simulated, independent of the frame rate
+const LOGIC_TICKS_PER_SECOND: f64 = 20.;
 
 #[derive(Resource)]
 pub struct Map {
     blocks: HashMap<(i32, i32), (Entity, BlockType)>,
+    // where the pressure plates in the map are. it's kept up to date as they're spawned and
+    // despawned, so checking them every tick doesn't mean looking through every block
+    pressure_plates: HashSet<(i32, i32)>,
+    // where the chunk loaders and inserters in the map are, kept up to date the same way
+    chunk_loaders: HashSet<(i32, i32)>,
+    inserters: HashSet<(i32, i32)>,
 }
 
 #[derive(Resource)]
@@ -76,53 +111,92 @@ struct MousePosInWorld {
     pos: Vec2,
 }
 
-#[derive(Resource)]
-struct BlockUpdateQueue {
-    queue: VecDeque<Option<(i32, i32)>>,
+impl BlockGrid for Map {
+    fn get_block(&self, pos: (i32, i32)) -> Option<BlockType> {
+        self.blocks.get(&pos).map(|(_, block_type)| *block_type)
+    }
+
+    // only changes blocks that are already in the map, since it has no entity for new ones
+    fn set_block(&mut self, pos: (i32, i32), block_type: BlockType) {
+        if let Some((_, old_block_type)) = self.blocks.get_mut(&pos) {
+            *old_block_type = block_type;
+        }
+    }
 }
 
+#[derive(Resource, Deref, DerefMut)]
+struct BlockUpdateQueue(UpdateQueue);
+
 fn main() {
     println!();
     println!();
     println!("Hello! This is a survival, infinite world game, kind of like minecraft.");
-    println!("You can click g to start the game, and m to come back to the (currently non-existent) menu.");
+    println!("The menu lists your worlds. Pick one with the arrow keys and press g or enter to play it, or press n to make a new one. r renames, c copies, e exports its chunks as text files and delete deletes the picked world.");
+    println!(
+        "You can click m to save and come back to the menu. Worlds are saved in the saves folder."
+    );
     println!("You can use wasd to move around, left click near trees and rocks to gather resources and left click on blocks to break them.");
+    println!("The world has forests, plains, rocky hills, deserts and lakes. Deserts have cacti instead of trees, which also give wood, and rocky hills have big boulders full of stone. Wading through water is slow.");
+    println!("Rocky hills also have copper, iron and redstone ores, which you can gather like rocks. Ores can't be placed.");
     println!("You can use the numbers to select a particular inventory slot, and right click to place blocks.");
+    println!("Your inventory has 36 slots, and the first 9 are the hotbar. Press e to see all of them. Click a stack to pick it up and click again to put it down, or drag it to another slot. Right click picks up half a stack, or puts down one item.");
+    println!("Press q to throw one of the selected item on the ground, or shift q to throw the whole stack. Walk over items on the ground to pick them up. Anything you break or gather when your inventory is full is dropped too.");
+    println!("Chests hold 27 stacks of items. Right click one to open it next to your inventory, and move things in and out like in the inventory screen. Breaking a chest drops what's in it.");
+    println!("Conveyors carry items on the ground the way they're facing, and stop them at the end if there's a block in the way. Inserters move one item every half a second from behind them to in front of them, out of and into chests, conveyors or you if you're standing there. Both face away from you when placed, and stop while they're powered.");
+    println!("Press c to open the crafting menu, and click a recipe to craft it. The recipes are in assets/recipes.txt, so you can change them. Stacks hold up to 64 items, or 16 for RAM and chunk loaders, and RAM that has something stored in it doesn't stack.");
     println!("You can click esc to pause and unpause the game.");
     println!("There are currently not that many blocks, but I'll explain the ones that are in the game that you might have in your inventory:");
     println!("Wood and stone are the brown and grey ones. They don't do anything, except that stone can power redstone components");
     println!("wire just carries a signal from adjacent wires or repeaters or inverters or stone. Has a max power of 128, power decreases by 1 as you go along blocks");
+    println!("Directional wire is the thin one. It works like wire, but only connects to the blocks in front of and behind it, so you can run them side by side.");
     println!(
         "Repeaters will give off a power level of 128 if they receive power behind the arrow."
     );
     println!("Inverter will give off a power level of 128 only of there is no power behind it.");
     println!("Inverter and repeaters are directional, and you can place it in a particular direction by facing in that direction while placing it.");
+    println!("Levers, buttons and pressure plates power things next to them. Right click a lever to flip it, or a button to press it for a second. Pressure plates are on while you stand on them.");
+    println!("Lamps light up when any side is powered. Seven segment displays show a hex digit, where the top, right, bottom and left sides are worth 1, 2, 4 and 8.");
+    println!("Comparators output the signal strength behind them if it's at least as strong as the signals on their sides, and nothing otherwise. Right click one to make it subtract the side signal from the one behind instead.");
+    println!("SR latches turn on when their right side is powered and off when their left side is. D flip flops store the signal behind them when their right side turns on.");
+    println!("RAM has 16 cells. The right side picks the cell, going up by one every 8 signal strength, and while the left side is on it stores the signal behind it in that cell. It outputs the selected cell in front.");
+    println!("Chunk loaders keep the chunks around them running while you're far away. Chunks with circuits that are still switching keep running too, unless keep_chunks_with_pending_updates is set to false in the world's world.txt.");
+    println!("The world is saved every minute while you play. Set autosave_seconds in the world's world.txt to change how often.");
+    println!("AND, OR, XOR and NAND gates take their two inputs from their sides and output in front, in the direction of the arrow.");
+    println!("Repeaters and inverters take 1 tick (1/20th of a second) to switch. Gates do too. Right click on a placed one to change its delay, up to 4 ticks. Longer delays are shown darker.");
     println!("Anyways, that's all ive made so far, I'll add more thins soon(ish). Enjoy!");
     println!();
     println!();
     App::new()
         .add_plugins(DefaultPlugins)
         .add_plugins(InventoryUI)
+        .add_plugins(LoadErrorsUI)
+        .add_plugins(WorldMenuUI)
+        .insert_resource(CurrentWorld::default())
         .add_state::<AppState>()
         .add_state::<SimulationState>()
         .insert_resource(ClearColor(Color::rgb(0.3, 0.5, 0.15)))
         .insert_resource(ChunksToSave {
             chunks: HashMap::new(),
             chunks_loaded: HashSet::new(),
+            chunks_simulated: HashSet::new(),
         })
         .insert_resource(Map {
             blocks: HashMap::new(),
+            pressure_plates: HashSet::new(),
+            chunk_loaders: HashSet::new(),
+            inserters: HashSet::new(),
         })
+        .insert_resource(GroundMap::default())
+        .insert_resource(Recipes::default())
         .insert_resource(PlayerInventory {
-            items: HashMap::new(),
             selected_slot: 0,
             slots: vec![],
+            held_slot: None,
         })
         .insert_resource(MousePosInWorld { pos: Vec2::ZERO })
-        .insert_resource(BlockUpdateQueue {
-            queue: VecDeque::new(),
-        })
-        .add_systems(Startup, setup)
+        .insert_resource(BlockUpdateQueue(UpdateQueue::default()))
+        .insert_resource(Time::<Fixed>::from_hz(LOGIC_TICKS_PER_SECOND))
+        .add_systems(Startup, (setup, load_recipes))
         // .add_systems(Update, print_num_entites)
         .add_systems(
             Update,
@@ -131,14 +205,15 @@ fn main() {
                 entity_collide_static_circle.after(move_player),
                 update_physics_body_movement.after(entity_collide_static_circle),
                 move_camera,
-                block_placer_breaker_system,
+                block_placer_breaker_system.run_if(inventory_menus_closed),
                 entity_collide_block.after(move_player),
-                player_gather_collectible,
+                player_gather_collectible.run_if(inventory_menus_closed),
                 calculate_mouse_pos_in_world,
                 update_collectibles,
                 change_player_selected_slot,
-                update_processor_system,
-                // update_processor_system.run_if(on_timer(Duration::from_millis(50))),
+                drop_item_system.run_if(inventory_menus_closed),
+                update_dropped_items,
+                pick_up_dropped_items,
                 update_blocks,
                 unload_far_collectibles.run_if(on_timer(Duration::from_millis(500))),
                 // unload_far_trees.run_if(on_timer(Duration::from_millis(500))),
@@ -146,15 +221,51 @@ fn main() {
                 unload_far_blocks.run_if(on_timer(Duration::from_millis(500))),
                 unload_far_chunk_backgrounds.run_if(on_timer(Duration::from_millis(500))),
                 load_close_chunks.run_if(on_timer(Duration::from_millis(100))),
+                update_play_time,
+            )
+                .run_if(in_state(AppState::Game))
+                .run_if(in_state(SimulationState::Running)),
+        )
+        .add_systems(
+            Update,
+            (
+                move_items_on_conveyors,
+                inserter_system.run_if(on_timer(Duration::from_millis(500))),
             )
                 .run_if(in_state(AppState::Game))
                 .run_if(in_state(SimulationState::Running)),
         )
-        .add_systems(OnEnter(AppState::Game), spawn_players)
         .add_systems(
             Update,
             (
-                transition_to_game_state,
+                save_players,
+                autosave_chunks,
+                save_chunk_loaders,
+                save_current_world_metadata,
+            )
+                .run_if(in_state(AppState::Game))
+                .run_if(autosave_is_due),
+        )
+        .add_systems(
+            FixedUpdate,
+            (
+                update_pressure_plates,
+                update_processor_system.after(update_pressure_plates),
+            )
+                .run_if(in_state(AppState::Game))
+                .run_if(in_state(SimulationState::Running)),
+        )
+        .add_systems(
+            OnEnter(AppState::Game),
+            (
+                spawn_players,
+                load_chunk_loader_chunks,
+                save_current_world_metadata,
+            ),
+        )
+        .add_systems(
+            Update,
+            (
                 transition_to_main_menu_state,
                 game_pauser_system.run_if(in_state(AppState::Game)),
             ),
@@ -165,39 +276,28 @@ fn main() {
                 save_players.before(despawn_players),
                 despawn_players,
                 unload_all_collectibles,
+                save_chunk_loaders.before(unload_all_blocks),
                 unload_all_blocks,
+                unload_all_ground,
                 save_chunks_to_file
                     .after(unload_all_collectibles)
-                    .after(unload_all_blocks),
+                    .after(unload_all_blocks)
+                    .after(unload_all_ground),
                 unload_all_chunk_backgrounds_and_clear_chunks_to_save.after(save_chunks_to_file),
+                save_current_world_metadata,
             ),
         )
         .run();
 }
 
-pub fn transition_to_game_state(
-    mut commands: Commands,
-    keyboard_input: Res<Input<KeyCode>>,
-    app_state: Res<State<AppState>>,
-) {
-    if keyboard_input.just_pressed(KeyCode::G) {
-        if *app_state.get() != AppState::Game {
-            commands.insert_resource(NextState(Some(AppState::Game)));
-            println!("Entered AppState::Game");
-        }
-    }
-}
-
 pub fn transition_to_main_menu_state(
     mut commands: Commands,
     keyboard_input: Res<Input<KeyCode>>,
     app_state: Res<State<AppState>>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::M) {
-        if *app_state.get() != AppState::MainMenu {
-            commands.insert_resource(NextState(Some(AppState::MainMenu)));
-            println!("Entered AppState::MainMenu");
-        }
+    if keyboard_input.just_pressed(KeyCode::M) && *app_state.get() != AppState::MainMenu {
+        commands.insert_resource(NextState(Some(AppState::MainMenu)));
+        println!("Entered AppState::MainMenu");
     }
 }
 
@@ -256,94 +356,63 @@ fn calculate_mouse_pos_in_world(
     }
 }
 
+// puts items in the inventory, first on stacks of the same item and then in empty slots. the slot
+// kept for the stack held in the inventory screen is left alone. returns how many didn't fit
 fn insert_block_to_inventory(
-    inventory: &mut ResMut<PlayerInventory>,
+    inventory: &mut PlayerInventory,
     item_type: ItemType,
-    number: usize,
-) {
-    // dbg!(block_type);
-    // let item_type = remove_block_type_data_for_inventory(item_type);
-    // dbg!(block_type);
-
-    let count = *(inventory.items.get(&item_type).unwrap_or(&0));
-    // dbg!(count);
-    inventory.items.insert(item_type.clone(), count + number);
-
-    let mut empty_slot = inventory.slots.len();
-
-    for i in 0..inventory.slots.len() {
-        match &inventory.slots[i] {
-            Some(slot) => {
-                // dbg!(slot.item_type);
-                if slot.item_type == item_type {
-                    inventory.slots[i] = Some(InventorySlot {
-                        item_type,
-                        count: slot.count + number,
-                    });
-
-                    // dbg!("lmoa");
-
-                    return;
-                }
-            }
-            None => {
-                if empty_slot == inventory.slots.len() {
-                    empty_slot = i;
-                    // dbg!("lmoa2");
-                }
-            }
+    mut number: usize,
+    data: Option<String>,
+) -> usize {
+    let held_slot = inventory.held_slot;
+    let mut slots = inventory
+        .slots
+        .iter_mut()
+        .enumerate()
+        .filter(|(i, _)| held_slot != Some(*i))
+        .map(|(_, slot)| slot)
+        .collect::<Vec<_>>();
+    for slot in slots.iter_mut().filter_map(|slot| slot.as_mut()) {
+        if number > 0 && slot.can_stack_with(item_type, &data) && slot.count < slot.max_stack {
+            let added = number.min(slot.max_stack - slot.count);
+            slot.count += added;
+            number -= added;
         }
     }
-    if empty_slot == inventory.slots.len() {
-        inventory.slots.push(Some(InventorySlot {
-            item_type,
-            count: number,
-        }));
-        // dbg!("lmo3");
-    } else {
-        inventory.slots[empty_slot] = Some(InventorySlot {
-            item_type,
-            count: number,
-        });
-        // dbg!("lmoa4");
+    for slot in slots {
+        if number > 0 && slot.is_none() {
+            let new_slot = InventorySlot::new(item_type, 0, data.clone());
+            let added = number.min(new_slot.max_stack);
+            *slot = Some(InventorySlot {
+                count: added,
+                ..new_slot
+            });
+            number -= added;
+        }
     }
+    number
 }
 
-fn pop_block_from_current_slot(
-    inventory: &mut ResMut<PlayerInventory>,
-    dir: i32,
-) -> Option<BlockType> {
+// takes one item out of the selected slot, and returns the block to place for it. items that
+// aren't blocks stay in the slot
+fn pop_block_from_current_slot(inventory: &mut PlayerInventory, dir: i32) -> Option<BlockType> {
     let selected_slot = inventory.selected_slot;
-    if selected_slot >= inventory.slots.len() {
-        return None;
-    }
-    if let Some(slot) = &inventory.slots[selected_slot].clone() {
-        let &count_in_inventory = inventory.items.get(&slot.item_type).unwrap_or(&0);
-        let count_in_slot = slot.count;
-        let item_type = slot.item_type.clone();
-        let block_type = get_block_type_after_popped_from_inventory(item_type, dir);
-        if count_in_inventory > 1 && count_in_slot > 1 {
-            inventory.slots[selected_slot] = Some(InventorySlot {
-                item_type: item_type.clone(),
-                count: count_in_inventory - 1,
-            });
-            inventory.items.insert(item_type.clone(), count_in_slot - 1);
-            return Some(block_type);
-        } else {
-            inventory.slots[selected_slot] = None;
-            inventory.items.remove(&item_type);
-            return Some(block_type);
-        }
+    let slot = inventory.slots.get_mut(selected_slot)?;
+    let item_slot = slot.as_mut()?;
+    let block_type = get_block_type_after_popped_from_inventory(item_slot.item_type, dir)?;
+    let block_type = match &item_slot.data {
+        Some(data) => get_block_type_with_item_data(block_type, data),
+        None => block_type,
+    };
+    item_slot.count -= 1;
+    if item_slot.count == 0 {
+        *slot = None;
     }
-    None
+    Some(block_type)
 }
 
 fn create_block_update(pos: (i32, i32), block_update_queue: &mut ResMut<BlockUpdateQueue>) {
-    block_update_queue.queue.push_back(Some(pos));
-    block_update_queue.queue.push_back(Some((pos.0, pos.1 + 1)));
-    block_update_queue.queue.push_back(Some((pos.0, pos.1 - 1)));
-    block_update_queue.queue.push_back(Some((pos.0 + 1, pos.1)));
-    block_update_queue.queue.push_back(Some((pos.0 - 1, pos.1)));
+    block_update_queue.create_block_update(pos);
 }
 
 fn get_facing_dir(block_pos: (i32, i32), player_pos: Vec3) -> i32 {
@@ -374,6 +443,7 @@ fn get_facing_dir(block_pos: (i32, i32), player_pos: Vec3) -> i32 {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn block_placer_breaker_system(
     mut commands: Commands,
     mut block_map: ResMut<Map>,
@@ -384,6 +454,8 @@ fn block_placer_breaker_system(
     time: Res<Time>,
     mut block_update_queue: ResMut<BlockUpdateQueue>,
     asset_server: Res<AssetServer>,
+    chests: Query<&ChestInventory>,
+    mut inventory_screen: ResMut<InventoryScreen>,
 ) {
     let (player_transform, mut player) = player_transforms.get_single_mut().unwrap();
     let player_translation = player_transform.translation;
@@ -421,6 +493,15 @@ fn block_placer_breaker_system(
                         // dbg!(block_type);
                         create_block_update(pos, &mut block_update_queue);
                     }
+                } else if mouse.just_pressed(MouseButton::Right) {
+                    let (entity, block_type) = *block_map.blocks.get(&pos).unwrap();
+                    // right clicking a chest opens it next to the inventory
+                    if block_type == BlockType::Chest {
+                        inventory_screen.chest = Some(entity);
+                    } else if let Some(new_block_type) = get_block_type_after_use(block_type) {
+                        block_map.blocks.insert(pos, (entity, new_block_type));
+                        create_block_update(pos, &mut block_update_queue);
+                    }
                 }
             } else {
                 player.place_cooldown -= time.delta_seconds();
@@ -429,12 +510,24 @@ fn block_placer_breaker_system(
             player.place_cooldown = 0.;
             if player.break_cooldown >= 0.5 {
                 player.break_cooldown = 0.;
-                if let Some(block_type) = despawn_block(commands, block_map, pos) {
-                    insert_block_to_inventory(
-                        &mut inventory,
-                        remove_block_type_data_for_inventory(block_type),
-                        1,
-                    );
+                if let Some(&(entity, BlockType::Chest)) = block_map.blocks.get(&pos) {
+                    if let Ok(chest) = chests.get(entity) {
+                        drop_chest_contents(&mut commands, &asset_server, pos, chest);
+                    }
+                }
+                if let Some(block_type) = despawn_block(&mut commands, &mut block_map, pos) {
+                    let item_type = remove_block_type_data_for_inventory(block_type);
+                    let data = get_item_data_from_block_type(block_type);
+                    // if there's no room for the block, it's dropped where it was
+                    if insert_block_to_inventory(&mut inventory, item_type, 1, data.clone()) > 0 {
+                        spawn_dropped_item(
+                            &mut commands,
+                            &asset_server,
+                            Vec2::new(pos.0 as f32, pos.1 as f32),
+                            InventorySlot::new(item_type, 1, data),
+                            0.,
+                        );
+                    }
                     create_block_update(pos, &mut block_update_queue);
                 }
             } else if let Some(entry) = block_map.blocks.get(&pos) {
@@ -450,266 +543,75 @@ fn block_placer_breaker_system(
     }
 }
 
-// updates an entire connected component of wire
-fn update_wire_line(
-    init_pos: (i32, i32),
-    block_map: &mut ResMut<Map>,
-    block_update_queue: &mut ResMut<BlockUpdateQueue>,
+// system that runs the circuit simulation once every logic tick. the simulation doesn't print
+// anything itself, so blocks that get caught in an update loop and ticks that start running out
+// of updates are reported here, once each
+fn update_processor_system(
+    mut block_update_queue: ResMut<BlockUpdateQueue>,
+    mut block_map: ResMut<Map>,
 ) {
-    let mut queue = VecDeque::new();
-    let mut to_update_next = HashSet::new();
-    queue.push_back(init_pos);
-    while !queue.is_empty() {
-        let pos = queue.pop_front().unwrap();
-        let new_entity;
-
-        if let Some((entity, block_type)) = block_map.blocks.get(&pos) {
-            new_entity = *entity;
-            match block_type {
-                BlockType::Wire(old_power) => {
-                    // new_block_type = BlockType::Wire(0);
-                    let mut new_power = 0;
-                    let old_power = *old_power;
-
-                    let mut set_new_power = |block_type1, inc_dir| match block_type1 {
-                        BlockType::Stone(_) => {
-                            new_power = 128;
-                        }
-                        BlockType::Wire(power) => new_power = (power - 1).max(new_power),
-                        BlockType::Repeater(power, dir) => {
-                            if dir != (inc_dir + 2) % 4 {
-                                new_power = 128.min(128 * power).max(new_power);
-                            }
-                        }
-                        BlockType::Inverter(power, dir) => {
-                            if dir != (inc_dir + 2) % 4 {
-                                new_power = 128.min(128 * power).max(new_power);
-                            }
-                        }
-                        _ => {}
-                    };
-
-                    if let Some((_, block_type1)) = block_map.blocks.get(&(pos.0, pos.1 + 1)) {
-                        set_new_power(*block_type1, 0);
-                    }
-                    if let Some((_, block_type1)) = block_map.blocks.get(&(pos.0, pos.1 - 1)) {
-                        set_new_power(*block_type1, 2);
-                    }
-                    if let Some((_, block_type1)) = block_map.blocks.get(&(pos.0 + 1, pos.1)) {
-                        set_new_power(*block_type1, 1);
-                    }
-                    if let Some((_, block_type1)) = block_map.blocks.get(&(pos.0 - 1, pos.1)) {
-                        set_new_power(*block_type1, 3);
-                    }
-                    if new_power != old_power {
-                        block_map
-                            .blocks
-                            .insert(pos, (new_entity, BlockType::Wire(new_power)));
-                        // create_block_update(pos, block_update_queue);
-                        queue.push_back(pos);
-                        queue.push_back((pos.0, pos.1 + 1));
-                        queue.push_back((pos.0, pos.1 - 1));
-                        queue.push_back((pos.0 + 1, pos.1));
-                        queue.push_back((pos.0 - 1, pos.1));
-                        to_update_next.insert((pos.0, pos.1 + 1));
-                        to_update_next.insert((pos.0, pos.1 - 1));
-                        to_update_next.insert((pos.0 + 1, pos.1));
-                        to_update_next.insert((pos.0 - 1, pos.1));
-                    }
-                    to_update_next.remove(&pos);
-                }
-                _ => {}
-            }
+    let was_overloaded = block_update_queue.overloaded;
+    let flagged_before = block_update_queue
+        .flagged
+        .keys()
+        .copied()
+        .collect::<HashSet<(i32, i32)>>();
+    run_logic_tick(block_map.as_mut(), &mut block_update_queue);
+    for pos in block_update_queue.flagged.keys() {
+        if !flagged_before.contains(pos) {
+            println!("block at {}, {} is stuck in an update loop", pos.0, pos.1);
         }
     }
-    for update_pos in to_update_next {
-        block_update_queue.queue.push_back(Some(update_pos));
+    if block_update_queue.overloaded && !was_overloaded {
+        println!(
+            "too many block updates, carrying {} over to the next tick until it catches up",
+            block_update_queue.queue.len()
+        );
     }
 }
 
-// system that processes block updates, not updating the processor
-fn update_processor_system(
-    mut block_update_queue: ResMut<BlockUpdateQueue>,
+// turns pressure plates on while a player is standing on them, and off otherwise
+fn update_pressure_plates(
+    players: Query<(&Transform, &CollisionBox), With<Player>>,
     mut block_map: ResMut<Map>,
+    mut block_update_queue: ResMut<BlockUpdateQueue>,
 ) {
-    block_update_queue.queue.push_back(None);
-    // let mut done = HashSet::new();
-    while !block_update_queue.queue.is_empty() {
-        if let Some(pos) = block_update_queue.queue.pop_front().unwrap() {
-            // if done.contains(&pos) {
-            //     continue;
-            // }
-            // done.insert(pos);
-            // let mut new_block_type: BlockType;
-            let new_entity;
-
-            if let Some((entity, block_type)) = block_map.blocks.get(&pos) {
-                new_entity = *entity;
-                match block_type {
-                    BlockType::Wire(old_power) => {
-                        // new_block_type = BlockType::Wire(0);
-                        // let mut new_power = 0;
-                        // let old_power = *old_power;
-
-                        // let mut set_new_power = |block_type1, inc_dir| match block_type1 {
-                        //     BlockType::Stone(_) => {
-                        //         new_power = 128;
-                        //     }
-                        //     BlockType::Wire(power) => new_power = (power - 1).max(new_power),
-                        //     BlockType::Repeater(power, dir) => {
-                        //         if dir != (inc_dir + 2) % 4 {
-                        //             new_power = 128.min(128 * power).max(new_power);
-                        //         }
-                        //     }
-                        //     BlockType::Inverter(power, dir) => {
-                        //         if dir != (inc_dir + 2) % 4 {
-                        //             new_power = 128.min(128 * power).max(new_power);
-                        //         }
-                        //     }
-                        //     _ => {}
-                        // };
-
-                        // if let Some((_, block_type1)) = block_map.blocks.get(&(pos.0, pos.1 + 1)) {
-                        //     set_new_power(*block_type1, 0);
-                        // }
-                        // if let Some((_, block_type1)) = block_map.blocks.get(&(pos.0, pos.1 - 1)) {
-                        //     set_new_power(*block_type1, 2);
-                        // }
-                        // if let Some((_, block_type1)) = block_map.blocks.get(&(pos.0 + 1, pos.1)) {
-                        //     set_new_power(*block_type1, 1);
-                        // }
-                        // if let Some((_, block_type1)) = block_map.blocks.get(&(pos.0 - 1, pos.1)) {
-                        //     set_new_power(*block_type1, 3);
-                        // }
-                        update_wire_line(pos, &mut block_map, &mut block_update_queue);
-                        // if new_power != old_power {
-                        // block_map
-                        //     .blocks
-                        //     .insert(pos, (new_entity, BlockType::Wire(new_power)));
-                        // create_block_update(pos, &mut block_update_queue);
-                        // }
-                    }
-                    BlockType::Repeater(old_power, dir) => {
-                        // new_block_type = BlockType::Wire(0);
-                        let mut new_power = 0;
-                        let old_power = *old_power;
-                        let dir = *dir;
-
-                        let mut set_new_power = |block_type1, inc_dir| match block_type1 {
-                            BlockType::Stone(_) => {
-                                if dir == inc_dir {
-                                    new_power = 1;
-                                }
-                            }
-                            BlockType::Wire(power) => {
-                                if inc_dir == dir {
-                                    new_power = 1.min(power);
-                                }
-                            }
-                            BlockType::Repeater(power, other_dir) => {
-                                if (other_dir + 2) % 4 != inc_dir && dir == inc_dir {
-                                    new_power = power;
-                                }
-                            }
-                            BlockType::Inverter(power, other_dir) => {
-                                if (other_dir + 2) % 4 != inc_dir && dir == inc_dir {
-                                    new_power = power;
-                                }
-                            }
-                            _ => {}
-                        };
-
-                        if let Some((_, block_type1)) = block_map.blocks.get(&(pos.0, pos.1 + 1)) {
-                            set_new_power(*block_type1, 0);
-                        }
-                        if let Some((_, block_type1)) = block_map.blocks.get(&(pos.0, pos.1 - 1)) {
-                            set_new_power(*block_type1, 2);
-                        }
-                        if let Some((_, block_type1)) = block_map.blocks.get(&(pos.0 + 1, pos.1)) {
-                            set_new_power(*block_type1, 1);
-                        }
-                        if let Some((_, block_type1)) = block_map.blocks.get(&(pos.0 - 1, pos.1)) {
-                            set_new_power(*block_type1, 3);
-                        }
-                        block_map
-                            .blocks
-                            .insert(pos, (new_entity, BlockType::Repeater(new_power, dir)));
-                        if new_power != old_power {
-                            create_block_update(pos, &mut block_update_queue);
-                        }
-                    }
-                    BlockType::Inverter(old_power, dir) => {
-                        // new_block_type = BlockType::Wire(0);
-                        let mut new_power = 1;
-                        let old_power = *old_power;
-                        let dir = *dir;
-
-                        let mut set_new_power = |block_type1, inc_dir| match block_type1 {
-                            BlockType::Stone(_) => {
-                                if dir == inc_dir {
-                                    new_power = 0;
-                                }
-                            }
-                            BlockType::Wire(power) => {
-                                if inc_dir == dir {
-                                    new_power = 1 - 1.min(power);
-                                }
-                            }
-                            BlockType::Repeater(power, other_dir) => {
-                                if (other_dir + 2) % 4 != inc_dir && dir == inc_dir {
-                                    new_power = 1 - power;
-                                }
-                            }
-                            BlockType::Inverter(power, other_dir) => {
-                                if (other_dir + 2) % 4 != inc_dir && dir == inc_dir {
-                                    new_power = 1 - power;
-                                }
-                            }
-                            // BlockType::Repeater(power, other_dir) => new_power = 128.min(128 * power),
-                            _ => {}
-                        };
-
-                        if let Some((_, block_type1)) = block_map.blocks.get(&(pos.0, pos.1 + 1)) {
-                            set_new_power(*block_type1, 0);
-                        }
-                        if let Some((_, block_type1)) = block_map.blocks.get(&(pos.0, pos.1 - 1)) {
-                            set_new_power(*block_type1, 2);
-                        }
-                        if let Some((_, block_type1)) = block_map.blocks.get(&(pos.0 + 1, pos.1)) {
-                            set_new_power(*block_type1, 1);
-                        }
-                        if let Some((_, block_type1)) = block_map.blocks.get(&(pos.0 - 1, pos.1)) {
-                            set_new_power(*block_type1, 3);
-                        }
-                        block_map
-                            .blocks
-                            .insert(pos, (new_entity, BlockType::Inverter(new_power, dir)));
-                        if new_power != old_power {
-                            create_block_update(pos, &mut block_update_queue);
-                        }
-                    }
-                    _ => {}
-                }
-            }
-            // if should_insert {
-            //     block_map.blocks.insert(pos, (new_entity, new_block_type));
-            // }
-        } else {
-            break;
+    let plates = block_map
+        .pressure_plates
+        .iter()
+        .filter_map(|&pos| match block_map.get_block(pos) {
+            Some(BlockType::PressurePlate(power)) => Some((pos, power)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    for (pos, power) in plates {
+        let pressed = players.iter().any(|(transform, coll_box)| {
+            (transform.translation.x - pos.0 as f32).abs() < 0.5 + coll_box.width / 2.
+                && (transform.translation.y - pos.1 as f32).abs() < 0.5 + coll_box.height / 2.
+        }) as i32;
+        if pressed != power {
+            block_map.set_block(pos, BlockType::PressurePlate(pressed));
+            create_block_update(pos, &mut block_update_queue);
         }
     }
 }
 
-// update the texture and color of blocks based on their state
+// update the texture and color of blocks based on their state. blocks caught in an update loop
+// are shown in magenta
 fn update_blocks(
     block_map: Res<Map>,
+    block_update_queue: Res<BlockUpdateQueue>,
     mut blocks: Query<(&mut Sprite, &mut Handle<Image>, &BlockEntity)>,
     asset_server: Res<AssetServer>,
 ) {
     for (mut sprite, mut img, block_entity) in &mut blocks {
         let pos = block_entity.pos;
         if let Some((_, block_type)) = block_map.blocks.get(&pos) {
-            sprite.color = get_block_color(*block_type);
+            sprite.color = if block_update_queue.flagged.contains_key(&pos) {
+                Color::rgb(1., 0., 1.)
+            } else {
+                get_block_color(*block_type)
+            };
             *img = get_block_texture(*block_type, &asset_server);
         }
     }
@@ -718,8 +620,10 @@ fn update_blocks(
 fn update_physics_body_movement(
     time: Res<Time>,
     mut bodies: Query<(&mut Transform, &mut PhysicsBody)>,
+    ground_map: Res<GroundMap>,
 ) {
     for (mut transform, mut body) in &mut bodies {
+        let drag = DRAG * get_ground_drag(ground_map.get_tile(transform.translation.truncate()));
         let acc = body.acc;
         body.vel += acc * time.delta_seconds();
         transform.translation += time.delta_seconds() * body.vel.extend(0.);
@@ -736,7 +640,7 @@ fn update_physics_body_movement(
 
         let vel = body.vel;
 
-        body.vel -= vel * (vel.distance(Vec2::ZERO) + 1.) * DRAG * time.delta_seconds() / 100.;
+        body.vel -= vel * (vel.distance(Vec2::ZERO) + 1.) * drag * time.delta_seconds() / 100.;
         transform.translation.z = 0.05;
     }
 }
@@ -762,13 +666,13 @@ fn entity_collide_block(
 
         let mut need_to_check_corners = true;
 
-        for i in 0..5 {
+        for offset in &to_check[..5] {
             let block_pos = (
-                body_pos.x.round() as i32 + to_check[i].0,
-                body_pos.y.round() as i32 + to_check[i].1,
+                body_pos.x.round() as i32 + offset.0,
+                body_pos.y.round() as i32 + offset.1,
             );
             if block_map.blocks.contains_key(&block_pos) {
-                let block_entity = block_map.blocks.get(&block_pos).unwrap().clone();
+                let block_entity = *block_map.blocks.get(&block_pos).unwrap();
                 match block_entity.1 {
                     BlockType::Wood => {}
                     BlockType::Stone(_) => {}
@@ -807,16 +711,16 @@ fn entity_collide_block(
                 }
             }
         }
-        for i in 5..9 {
+        for offset in &to_check[5..] {
             if !need_to_check_corners {
                 break;
             }
             let block_pos = (
-                body_pos.x.round() as i32 + to_check[i].0,
-                body_pos.y.round() as i32 + to_check[i].1,
+                body_pos.x.round() as i32 + offset.0,
+                body_pos.y.round() as i32 + offset.1,
             );
             if block_map.blocks.contains_key(&block_pos) {
-                let block_entity = block_map.blocks.get(&block_pos).unwrap().clone();
+                let block_entity = *block_map.blocks.get(&block_pos).unwrap();
                 match block_entity.1 {
                     BlockType::Wood => {}
                     BlockType::Stone(_) => {}
@@ -891,6 +795,7 @@ fn update_collectibles(
     }
 }
 
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 fn player_gather_collectible(
     mut commands: Commands,
     mut player: Query<(&Transform, &mut Player)>,
@@ -902,6 +807,7 @@ fn player_gather_collectible(
     >,
     mouse_world: ResMut<MousePosInWorld>,
     time: Res<Time>,
+    asset_server: Res<AssetServer>,
 ) {
     let (player_transform, mut player) = player.get_single_mut().unwrap();
     if mouse.pressed(MouseButton::Left) {
@@ -915,7 +821,17 @@ fn player_gather_collectible(
                 if collectible_direction.distance(Vec2::ZERO) < collectible.gather_radius
                     && collectible_direction.normalize().dot(player_direction) > 0.1
                 {
-                    insert_block_to_inventory(&mut inventory, collectible.item_type.clone(), 1);
+                    // if there's no room, what was gathered falls on the ground in front of the player
+                    if insert_block_to_inventory(&mut inventory, collectible.item_type, 1, None) > 0
+                    {
+                        spawn_dropped_item(
+                            &mut commands,
+                            &asset_server,
+                            player_transform.translation.truncate() + collectible_direction / 2.,
+                            InventorySlot::new(collectible.item_type, 1, None),
+                            0.,
+                        );
+                    }
                     collectible.uses -= 1;
                     if collectible.uses == 0 {
                         commands.entity(entity).despawn();
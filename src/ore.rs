@@ -0,0 +1,213 @@
+use crate::{
+    get_biome, get_chunk_rng, get_item_color, parse_word, Biome, Collectible, ItemType,
+    ParseErrorReason, StaticCollisionCircle, ORE_SALT,
+};
+use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
+use rand::Rng;
+
+// a deposit of copper, iron or redstone dust. they're mostly found in rocky hills
+#[derive(Component)]
+pub struct Ore {
+    pub ore_type: OreType,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OreType {
+    Copper,
+    Iron,
+    Redstone,
+}
+
+// the item an ore gives when it's gathered
+fn get_item_type_from_ore_type(ore_type: OreType) -> ItemType {
+    match ore_type {
+        OreType::Copper => ItemType::Copper,
+        OreType::Iron => ItemType::Iron,
+        OreType::Redstone => ItemType::RedstoneDust,
+    }
+}
+
+pub fn spawn_ore(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    pos: Vec3,
+    ore_type: OreType,
+    uses: usize,
+) {
+    let item_type = get_item_type_from_ore_type(ore_type);
+    // ores look like rocks tinted with the colour of what they give
+    let color = get_item_color(item_type);
+    let normal_color = Color::rgb(0.4, 0.4, 0.4) * 0.5 + color * 0.5;
+    let normal_material = materials.add(ColorMaterial::from(normal_color));
+    let punched_material = materials.add(ColorMaterial::from(normal_color * 1.3));
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: meshes.add(shape::Circle::new(0.8).into()).into(),
+            material: materials.add(ColorMaterial::from(normal_color)),
+            transform: Transform::from_translation(pos),
+            ..default()
+        },
+        Ore { ore_type },
+        Collectible {
+            pos: Vec2::new(pos.x, pos.y),
+            gather_radius: 3.,
+            item_type,
+            uses,
+            normal_material,
+            punched_material,
+        },
+        StaticCollisionCircle { radius: 0.8 },
+    ));
+}
+
+fn get_string_rep_from_ore_type(ore_type: OreType) -> &'static str {
+    match ore_type {
+        OreType::Copper => "copper_ore",
+        OreType::Iron => "iron_ore",
+        OreType::Redstone => "redstone_ore",
+    }
+}
+
+fn get_ore_type_from_string_rep(ore_type: &str) -> Result<OreType, ParseErrorReason> {
+    match ore_type {
+        "copper_ore" => Ok(OreType::Copper),
+        "iron_ore" => Ok(OreType::Iron),
+        "redstone_ore" => Ok(OreType::Redstone),
+        _ => Err(ParseErrorReason::UnknownType(String::from(ore_type))),
+    }
+}
+
+pub fn make_string_rep_of_ore(collectible: &Collectible, ore: &Ore) -> String {
+    format!(
+        "{} {} {} {}\n",
+        get_string_rep_from_ore_type(ore.ore_type),
+        collectible.pos.x,
+        collectible.pos.y,
+        collectible.uses
+    )
+}
+
+pub fn spawn_ore_from_string_rep(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    parts: Vec<&str>, // a vector of strings, containing the words in the string representation
+) -> Result<(), ParseErrorReason> {
+    let ore_type = get_ore_type_from_string_rep(parts[0])?;
+    let x = parse_word::<f32>(&parts, 1)?;
+    let y = parse_word::<f32>(&parts, 2)?;
+    let uses = parse_word::<usize>(&parts, 3)?;
+    spawn_ore(
+        commands,
+        meshes,
+        materials,
+        Vec3::new(x, y, 0.07),
+        ore_type,
+        uses,
+    );
+    Ok(())
+}
+
+// the chance of each try at placing an ore in a chunk working, depending on the biome it lands in
+fn get_ore_chance(biome: Biome) -> f32 {
+    match biome {
+        Biome::RockyHills => 0.35,
+        Biome::Desert => 0.05,
+        Biome::Forest | Biome::Plains | Biome::Lake => 0.,
+    }
+}
+
+pub fn generate_ores(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    pos: (i32, i32),
+    seed: u64,
+) {
+    let mut rng = get_chunk_rng(seed, pos, ORE_SALT);
+    for _ in 0..6 {
+        let pos = Vec3::new(
+            ((rng.gen::<f32>()) + pos.0 as f32) * 16.,
+            ((rng.gen::<f32>()) + pos.1 as f32) * 16.,
+            0.07,
+        );
+        // copper is the most common, then iron, then redstone dust
+        let kind = rng.gen::<f32>();
+        let ore_type = if kind < 0.45 {
+            OreType::Copper
+        } else if kind < 0.8 {
+            OreType::Iron
+        } else {
+            OreType::Redstone
+        };
+        if rng.gen::<f32>() >= get_ore_chance(get_biome(seed, pos.truncate())) {
+            continue;
+        }
+        spawn_ore(commands, meshes, materials, pos, ore_type, 6);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    // the ores generate_ores puts in each of the chunks, in the order they were spawned
+    fn generate_ores_in(seed: u64, chunks: &[(i32, i32)]) -> Vec<(Vec2, OreType)> {
+        let mut world = World::new();
+        world.insert_resource(Assets::<Mesh>::default());
+        world.insert_resource(Assets::<ColorMaterial>::default());
+        for &pos in chunks {
+            world.run_system_once(
+                move |mut commands: Commands,
+                      mut meshes: ResMut<Assets<Mesh>>,
+                      mut materials: ResMut<Assets<ColorMaterial>>| {
+                    generate_ores(&mut commands, &mut meshes, &mut materials, pos, seed);
+                },
+            );
+        }
+        let mut ores = world.query::<(&Collectible, &Ore)>();
+        ores.iter(&world)
+            .map(|(collectible, ore)| (collectible.pos, ore.ore_type))
+            .collect()
+    }
+
+    #[test]
+    fn ore_names_round_trip() {
+        for ore_type in [OreType::Copper, OreType::Iron, OreType::Redstone] {
+            assert_eq!(
+                get_ore_type_from_string_rep(get_string_rep_from_ore_type(ore_type)),
+                Ok(ore_type)
+            );
+        }
+        assert_eq!(
+            get_ore_type_from_string_rep("gold_ore"),
+            Err(ParseErrorReason::UnknownType(String::from("gold_ore")))
+        );
+    }
+
+    #[test]
+    fn ores_are_mostly_in_rocky_hills() {
+        assert!(get_ore_chance(Biome::RockyHills) > get_ore_chance(Biome::Desert));
+        assert!(get_ore_chance(Biome::Desert) > 0.);
+        for biome in [Biome::Forest, Biome::Plains, Biome::Lake] {
+            assert_eq!(get_ore_chance(biome), 0.);
+        }
+    }
+
+    // the same seed and chunk always get the same ores, and only where they can be
+    #[test]
+    fn ore_generation_is_the_same_every_time() {
+        let chunks = (0..8)
+            .flat_map(|x| (0..8).map(move |y| (x, y)))
+            .collect::<Vec<_>>();
+        let ores = generate_ores_in(12345, &chunks);
+        assert!(!ores.is_empty());
+        assert_eq!(generate_ores_in(12345, &chunks), ores);
+        assert_ne!(generate_ores_in(54321, &chunks), ores);
+        for (pos, _) in ores {
+            assert!(get_ore_chance(get_biome(12345, pos)) > 0.);
+        }
+    }
+}
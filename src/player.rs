@@ -1,8 +1,11 @@
-use std::collections::HashMap;
-
 use bevy::prelude::*;
 
-use crate::{BlockType, ItemType, PhysicsBody, PLAYER_ACCELERATION};
+use crate::{ItemType, PhysicsBody, PLAYER_ACCELERATION};
+
+// how many slots the player's inventory has. the first 9 are the hotbar
+pub const INVENTORY_SIZE: usize = 36;
+// the most items of one kind that fit in a slot, unless get_max_stack says otherwise
+pub const DEFAULT_MAX_STACK: usize = 64;
 
 #[derive(Component)]
 pub struct Player {
@@ -11,17 +14,98 @@ pub struct Player {
     pub place_cooldown: f32,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, PartialEq, Debug)]
 pub struct InventorySlot {
     pub item_type: ItemType,
     pub count: usize,
+    pub max_stack: usize,
+    // anything else the item remembers, in the same format it's saved in, like the cells of a ram
+    // block that was broken. items only stack with items that have the same data
+    pub data: Option<String>,
 }
 
-#[derive(Resource)]
+impl InventorySlot {
+    pub fn new(item_type: ItemType, count: usize, data: Option<String>) -> Self {
+        InventorySlot {
+            item_type,
+            count,
+            max_stack: get_max_stack(item_type, &data),
+            data,
+        }
+    }
+
+    pub fn can_stack_with(&self, item_type: ItemType, data: &Option<String>) -> bool {
+        self.item_type == item_type && self.data == *data
+    }
+}
+
+// the slots are the only place the inventory is kept, so the number of an item the player has is
+// always the number in its slots
+#[derive(Resource, Clone)]
 pub struct PlayerInventory {
-    pub items: HashMap<ItemType, usize>,
     pub selected_slot: usize,
     pub slots: Vec<Option<InventorySlot>>,
+    // the slot the stack held in the inventory screen was picked up from. nothing else is put in it
+    // until that stack is put back, so picking things up meanwhile can't leave it nowhere to go
+    pub held_slot: Option<usize>,
+}
+
+impl PlayerInventory {
+    // how many of an item (without any data) are in the inventory altogether
+    pub fn count(&self, item_type: ItemType) -> usize {
+        self.slots
+            .iter()
+            .flatten()
+            .filter(|slot| slot.can_stack_with(item_type, &None))
+            .map(|slot| slot.count)
+            .sum()
+    }
+
+    // takes some of an item (without any data) out of the inventory, from the last slots first so
+    // the hotbar is emptied last. returns false and takes nothing if there aren't enough
+    pub fn remove(&mut self, item_type: ItemType, mut number: usize) -> bool {
+        if self.count(item_type) < number {
+            return false;
+        }
+        for slot in self.slots.iter_mut().rev() {
+            let Some(item_slot) = slot else {
+                continue;
+            };
+            if number == 0 || !item_slot.can_stack_with(item_type, &None) {
+                continue;
+            }
+            let taken = number.min(item_slot.count);
+            item_slot.count -= taken;
+            number -= taken;
+            if item_slot.count == 0 {
+                *slot = None;
+            }
+        }
+        true
+    }
+
+    // whether at least one of an item would fit, either on a stack of it or in an empty slot
+    pub fn has_room_for(&self, item_type: ItemType, data: &Option<String>) -> bool {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| self.held_slot != Some(*i))
+            .any(|(_, slot)| match slot {
+                Some(slot) => slot.can_stack_with(item_type, data) && slot.count < slot.max_stack,
+                None => true,
+            })
+    }
+}
+
+// items that hold data never stack, since every one is different. big blocks stack less
+pub fn get_max_stack(item_type: ItemType, data: &Option<String>) -> usize {
+    if data.is_some() {
+        return 1;
+    }
+    match item_type {
+        ItemType::Ram | ItemType::ChunkLoader => 16,
+        _ => DEFAULT_MAX_STACK,
+    }
 }
 
 pub fn change_player_selected_slot(
@@ -0,0 +1,461 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{Read, Seek, SeekFrom, Write},
+};
+
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+
+use crate::{
+    get_regions_dir, get_text_export_dir, make_chunk_file_header, migrate_chunk_lines, parse_word,
+    write_file_atomically, CHUNK_FORMAT_VERSION,
+};
+
+// regions are squares of REGION_SIZE by REGION_SIZE chunks that are saved together in one file
+pub const REGION_SIZE: i32 = 32;
+// whether chunks are compressed when they're saved. compressed and uncompressed chunks can both
+// always be loaded, so this can be changed without breaking old worlds
+const COMPRESS_CHUNKS: bool = true;
+const REGION_MAGIC: &[u8; 4] = b"BGRG";
+const REGION_FORMAT_VERSION: u32 = 1;
+// the magic and version, then an offset and a length for every chunk in the region. an offset of
+// 0 means the chunk isn't in the region
+const REGION_HEADER_SIZE: usize = 8 + 8 * (REGION_SIZE * REGION_SIZE) as usize;
+
+// blocks are saved as their index in here instead of their name. only ever add to the end, or old
+// regions will load with the wrong blocks
+const BLOCK_NAMES: [&str; 23] = [
+    "wood",
+    "stone",
+    "wire",
+    "directional_wire",
+    "repeater",
+    "inverter",
+    "and_gate",
+    "or_gate",
+    "xor_gate",
+    "nand_gate",
+    "lever",
+    "button",
+    "pressure_plate",
+    "lamp",
+    "seven_segment_display",
+    "comparator",
+    "sr_latch",
+    "d_flip_flop",
+    "ram",
+    "chunk_loader",
+    "chest",
+    "conveyor",
+    "inserter",
+];
+
+// what kind of line comes next in a packed chunk. lines that can't be packed, like blocks with
+// data that isn't numbers, are kept as text
+const BLOCK_LINE: u8 = 0;
+const TREE_LINE: u8 = 1;
+const ROCK_LINE: u8 = 2;
+const TEXT_LINE: u8 = 3;
+const COLLECTIBLE_LINE: u8 = 4;
+
+// collectibles other than trees and rocks are saved as their index in here, followed by the same
+// data as a tree or rock. only ever add to the end, like BLOCK_NAMES
+const COLLECTIBLE_NAMES: [&str; 5] = [
+    "cactus",
+    "boulder",
+    "copper_ore",
+    "iron_ore",
+    "redstone_ore",
+];
+
+// the region a chunk is in, and the chunk's index in that region's offset table
+pub fn get_region_of_chunk(pos: (i32, i32)) -> ((i32, i32), usize) {
+    let region = (pos.0.div_euclid(REGION_SIZE), pos.1.div_euclid(REGION_SIZE));
+    let index = pos.1.rem_euclid(REGION_SIZE) * REGION_SIZE + pos.0.rem_euclid(REGION_SIZE);
+    (region, index as usize)
+}
+
+// the chunk at an index in a region's offset table
+pub fn get_chunk_in_region(region: (i32, i32), index: usize) -> (i32, i32) {
+    let index = index as i32;
+    (
+        region.0 * REGION_SIZE + index % REGION_SIZE,
+        region.1 * REGION_SIZE + index / REGION_SIZE,
+    )
+}
+
+// checks the magic and version at the start of a region file. file_length is the length of the
+// whole file, so a file that's too short to have an offset table isn't read any further
+fn check_region_header(start: &[u8], file_length: usize) -> Result<(), String> {
+    if file_length < REGION_HEADER_SIZE || &start[0..4] != REGION_MAGIC {
+        return Err(String::from("not a region file"));
+    }
+    let version = u32::from_le_bytes(start[4..8].try_into().unwrap());
+    if version > REGION_FORMAT_VERSION {
+        return Err(format!(
+            "saved by a newer version of the game (region format {})",
+            version
+        ));
+    }
+    Ok(())
+}
+
+// where a chunk is in a region file and how long it is, from its 8 bytes in the offset table.
+// None if the chunk isn't in the region
+fn parse_region_entry(
+    entry: &[u8],
+    index: usize,
+    file_length: usize,
+) -> Result<Option<(usize, usize)>, String> {
+    let offset = u32::from_le_bytes(entry[0..4].try_into().unwrap()) as usize;
+    let length = u32::from_le_bytes(entry[4..8].try_into().unwrap()) as usize;
+    if offset == 0 {
+        return Ok(None);
+    }
+    if offset + length > file_length {
+        return Err(format!("chunk {} goes past the end of the file", index));
+    }
+    Ok(Some((offset, length)))
+}
+
+// reads the packed chunks in a region file, by their index. a region that hasn't been saved yet
+// has no chunks
+pub fn read_region(file: &str) -> Result<HashMap<usize, Vec<u8>>, String> {
+    let mut chunks = HashMap::new();
+    let Ok(bytes) = fs::read(file) else {
+        return Ok(chunks);
+    };
+    check_region_header(&bytes[0..8.min(bytes.len())], bytes.len())?;
+    for index in 0..(REGION_SIZE * REGION_SIZE) as usize {
+        let entry = 8 + index * 8;
+        if let Some((offset, length)) =
+            parse_region_entry(&bytes[entry..entry + 8], index, bytes.len())?
+        {
+            chunks.insert(index, bytes[offset..offset + length].to_vec());
+        }
+    }
+    Ok(chunks)
+}
+
+pub fn write_region(file: &str, chunks: &HashMap<usize, Vec<u8>>) -> std::io::Result<()> {
+    let mut bytes = Vec::with_capacity(REGION_HEADER_SIZE);
+    bytes.extend_from_slice(REGION_MAGIC);
+    bytes.extend_from_slice(&REGION_FORMAT_VERSION.to_le_bytes());
+    bytes.resize(REGION_HEADER_SIZE, 0);
+    let mut indices = chunks.keys().copied().collect::<Vec<usize>>();
+    indices.sort();
+    for index in indices {
+        let chunk = &chunks[&index];
+        let entry = 8 + index * 8;
+        let offset = bytes.len() as u32;
+        bytes[entry..entry + 4].copy_from_slice(&offset.to_le_bytes());
+        bytes[entry + 4..entry + 8].copy_from_slice(&(chunk.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(chunk);
+    }
+    write_file_atomically(file, bytes)
+}
+
+// packs the lines of a chunk (in the current chunk format) into bytes. the first byte says whether
+// the rest is compressed
+pub fn pack_chunk(entities: &str) -> Vec<u8> {
+    let mut lines = Vec::new();
+    let mut line_count = 0;
+    for line in entities.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        pack_line(&mut lines, line);
+        line_count += 1;
+    }
+    let mut data = Vec::new();
+    write_varint(&mut data, CHUNK_FORMAT_VERSION as u64);
+    write_varint(&mut data, line_count);
+    data.extend_from_slice(&lines);
+
+    if COMPRESS_CHUNKS {
+        let mut encoder = DeflateEncoder::new(vec![1], Compression::default());
+        if encoder.write_all(&data).is_ok() {
+            if let Ok(packed) = encoder.finish() {
+                return packed;
+            }
+        }
+    }
+    let mut packed = vec![0];
+    packed.extend_from_slice(&data);
+    packed
+}
+
+// unpacks a chunk into the chunk format version it was saved with and its lines
+pub fn unpack_chunk(packed: &[u8]) -> Result<(u32, Vec<String>), String> {
+    let mut data = Vec::new();
+    match packed.first() {
+        Some(0) => data.extend_from_slice(&packed[1..]),
+        Some(1) => {
+            DeflateDecoder::new(&packed[1..])
+                .read_to_end(&mut data)
+                .map_err(|e| e.to_string())?;
+        }
+        _ => return Err(String::from("unknown compression")),
+    }
+
+    let mut reader = ByteReader {
+        bytes: &data,
+        pos: 0,
+    };
+    let version = reader.read_varint()? as u32;
+    let line_count = reader.read_varint()?;
+    let mut lines = Vec::new();
+    for _ in 0..line_count {
+        lines.push(unpack_line(&mut reader)?);
+    }
+    Ok((version, lines))
+}
+
+fn pack_line(bytes: &mut Vec<u8>, line: &str) {
+    let parts = line.split_whitespace().collect::<Vec<&str>>();
+    match parts[0] {
+        "block" if parts.len() >= 4 => {
+            let name = BLOCK_NAMES.iter().position(|name| *name == parts[1]);
+            let numbers = parts[2..]
+                .iter()
+                .map(|word| word.parse::<i64>())
+                .collect::<Result<Vec<i64>, _>>();
+            if let (Some(name), Ok(numbers)) = (name, numbers) {
+                bytes.push(BLOCK_LINE);
+                bytes.push(name as u8);
+                write_varint(bytes, numbers.len() as u64);
+                for number in numbers {
+                    write_signed_varint(bytes, number);
+                }
+                return;
+            }
+        }
+        "tree" | "rock" | "cactus" | "boulder" | "copper_ore" | "iron_ore" | "redstone_ore"
+            if parts.len() == 4 =>
+        {
+            if let (Ok(x), Ok(y), Ok(uses)) = (
+                parts[1].parse::<f32>(),
+                parts[2].parse::<f32>(),
+                parts[3].parse::<u64>(),
+            ) {
+                match parts[0] {
+                    "tree" => bytes.push(TREE_LINE),
+                    "rock" => bytes.push(ROCK_LINE),
+                    name => {
+                        bytes.push(COLLECTIBLE_LINE);
+                        bytes
+                            .push(COLLECTIBLE_NAMES.iter().position(|n| *n == name).unwrap() as u8);
+                    }
+                }
+                bytes.extend_from_slice(&x.to_le_bytes());
+                bytes.extend_from_slice(&y.to_le_bytes());
+                write_varint(bytes, uses);
+                return;
+            }
+        }
+        _ => {}
+    }
+    bytes.push(TEXT_LINE);
+    write_varint(bytes, line.len() as u64);
+    bytes.extend_from_slice(line.as_bytes());
+}
+
+fn unpack_line(reader: &mut ByteReader) -> Result<String, String> {
+    match reader.read_byte()? {
+        BLOCK_LINE => {
+            let name = BLOCK_NAMES
+                .get(reader.read_byte()? as usize)
+                .ok_or_else(|| String::from("unknown block"))?;
+            let count = reader.read_varint()?;
+            let mut numbers = Vec::new();
+            for _ in 0..count {
+                numbers.push(reader.read_signed_varint()?.to_string());
+            }
+            Ok(format!("block {} {}", name, numbers.join(" ")))
+        }
+        kind @ (TREE_LINE | ROCK_LINE | COLLECTIBLE_LINE) => {
+            let name = match kind {
+                TREE_LINE => "tree",
+                ROCK_LINE => "rock",
+                _ => COLLECTIBLE_NAMES
+                    .get(reader.read_byte()? as usize)
+                    .ok_or_else(|| String::from("unknown collectible"))?,
+            };
+            let x = f32::from_le_bytes(reader.read_bytes(4)?.try_into().unwrap());
+            let y = f32::from_le_bytes(reader.read_bytes(4)?.try_into().unwrap());
+            let uses = reader.read_varint()?;
+            Ok(format!("{} {} {} {}", name, x, y, uses))
+        }
+        TEXT_LINE => {
+            let length = reader.read_varint()? as usize;
+            String::from_utf8(reader.read_bytes(length)?.to_vec()).map_err(|e| e.to_string())
+        }
+        kind => Err(format!("unknown line kind {}", kind)),
+    }
+}
+
+// numbers are saved 7 bits at a time, with the top bit of each byte saying if there's more
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+// zigzag encoding, so small negative numbers stay small too
+fn write_signed_varint(bytes: &mut Vec<u8>, value: i64) {
+    write_varint(bytes, ((value << 1) ^ (value >> 63)) as u64);
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn read_bytes(&mut self, count: usize) -> Result<&'a [u8], String> {
+        if self.pos + count > self.bytes.len() {
+            return Err(String::from("the chunk ends too early"));
+        }
+        self.pos += count;
+        Ok(&self.bytes[self.pos - count..self.pos])
+    }
+
+    fn read_byte(&mut self) -> Result<u8, String> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_varint(&mut self) -> Result<u64, String> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_byte()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(String::from("number is too long"))
+    }
+
+    fn read_signed_varint(&mut self) -> Result<i64, String> {
+        let value = self.read_varint()?;
+        Ok((value >> 1) as i64 ^ -((value & 1) as i64))
+    }
+}
+
+// reads one packed chunk from a region file, or None if it isn't in there. only the header, the
+// chunk's entry in the offset table and the chunk itself are read, not the whole region
+pub fn read_region_chunk(file: &str, index: usize) -> Result<Option<Vec<u8>>, String> {
+    let Ok(mut reader) = fs::File::open(file) else {
+        return Ok(None);
+    };
+    let file_length = reader.metadata().map_err(|e| e.to_string())?.len() as usize;
+    let mut start = [0; 8];
+    if file_length >= start.len() {
+        reader.read_exact(&mut start).map_err(|e| e.to_string())?;
+    }
+    check_region_header(&start, file_length)?;
+
+    let mut entry = [0; 8];
+    reader
+        .seek(SeekFrom::Start((8 + index * 8) as u64))
+        .and_then(|_| reader.read_exact(&mut entry))
+        .map_err(|e| e.to_string())?;
+    let Some((offset, length)) = parse_region_entry(&entry, index, file_length)? else {
+        return Ok(None);
+    };
+    let mut chunk = vec![0; length];
+    reader
+        .seek(SeekFrom::Start(offset as u64))
+        .and_then(|_| reader.read_exact(&mut chunk))
+        .map_err(|e| e.to_string())?;
+    Ok(Some(chunk))
+}
+
+// writes every chunk in a world's regions out as a text chunk file in the current chunk format, so
+// they can be read and edited by hand. returns how many chunks were exported
+pub fn export_world_as_text(name: &str) -> Result<usize, String> {
+    let export_dir = get_text_export_dir(name);
+    fs::create_dir_all(&export_dir).map_err(|e| e.to_string())?;
+    let mut exported = 0;
+    let Ok(entries) = fs::read_dir(get_regions_dir(name)) else {
+        return Ok(exported);
+    };
+    for entry in entries.flatten() {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let parts = file_name
+            .trim_start_matches("r_")
+            .trim_end_matches(".bin")
+            .split('_')
+            .collect::<Vec<&str>>();
+        let (Ok(x), Ok(y)) = (parse_word::<i32>(&parts, 0), parse_word::<i32>(&parts, 1)) else {
+            continue;
+        };
+        let file = entry.path().to_string_lossy().to_string();
+        for (index, packed) in read_region(&file).map_err(|e| format!("{}: {}", file, e))? {
+            let pos = get_chunk_in_region((x, y), index);
+            let (version, lines) = unpack_chunk(&packed).map_err(|e| format!("{}: {}", file, e))?;
+            let entities = migrate_chunk_lines(version, lines).join("\n") + "\n";
+            let text = make_chunk_file_header(pos, &entities) + &entities;
+            fs::write(
+                format!("{}/chunk_{}_{}.txt", export_dir, pos.0, pos.1),
+                text,
+            )
+            .map_err(|e| e.to_string())?;
+            exported += 1;
+        }
+    }
+    Ok(exported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_keep_their_lines_when_packed_and_unpacked() {
+        let mut lines = Vec::new();
+        for (i, name) in BLOCK_NAMES.iter().enumerate() {
+            let i = i as i32;
+            lines.push(format!("block {} {} {} 1 {} 300", name, i - 11, 5 - i, i));
+        }
+        lines.push(String::from("tree 3.5 -7.25 2"));
+        lines.push(String::from("rock -0.5 12 0"));
+        for name in COLLECTIBLE_NAMES {
+            lines.push(format!("{} 1.5 -2.25 3", name));
+        }
+        // lines that can't be packed are kept as text
+        lines.push(String::from("item 2.5 3.5 wood 4 0"));
+        lines.push(String::from("chest_slot 1 2 0 stone 64"));
+        lines.push(String::from("ground 0 0 1 1 0 1"));
+        lines.push(String::from("block ram 1 2 0 0 ffff"));
+        lines.push(String::from("block not_a_block 1 2"));
+        lines.push(String::from("tree 1 2"));
+
+        let packed = pack_chunk(&lines.join("\n"));
+        assert_eq!(unpack_chunk(&packed), Ok((CHUNK_FORMAT_VERSION, lines)));
+    }
+
+    #[test]
+    fn chunks_are_read_back_from_their_region() {
+        let dir = std::env::temp_dir().join(format!("bevy_game_4_region_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("r_0_0.bin").to_string_lossy().to_string();
+        let mut chunks = HashMap::new();
+        chunks.insert(3, pack_chunk("tree 1 2 3"));
+        chunks.insert(700, pack_chunk("block wood 5 6"));
+        write_region(&file, &chunks).unwrap();
+
+        assert_eq!(read_region_chunk(&file, 3), Ok(Some(chunks[&3].clone())));
+        assert_eq!(
+            read_region_chunk(&file, 700),
+            Ok(Some(chunks[&700].clone()))
+        );
+        assert_eq!(read_region_chunk(&file, 4), Ok(None));
+        assert_eq!(read_region(&file), Ok(chunks));
+        let missing = dir.join("r_1_0.bin").to_string_lossy().to_string();
+        assert_eq!(read_region_chunk(&missing, 3), Ok(None));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,7 +1,10 @@
 use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
 use rand::Rng;
 
-use crate::{Collectible, ItemType, StaticCollisionCircle};
+use crate::{
+    get_biome, get_chunk_rng, parse_word, Biome, Collectible, ItemType, ParseErrorReason,
+    StaticCollisionCircle, ROCK_SALT,
+};
 
 #[derive(Component)]
 pub struct Rock {}
@@ -40,9 +43,9 @@ pub fn spawn_rock(
             pos: Vec2::new(pos.x, pos.y),
             gather_radius: 3.,
             item_type: ItemType::Stone(0),
-            uses: uses,
-            normal_material: normal_material,
-            punched_material: punched_material,
+            uses,
+            normal_material,
+            punched_material,
         },
         StaticCollisionCircle { radius: 1. },
     ));
@@ -65,30 +68,41 @@ pub fn spawn_rock_from_string_rep(
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<ColorMaterial>>,
     parts: Vec<&str>, // a vector of strings, containing the words in the string representation
-) {
-    let x = parts[1].parse::<f32>().unwrap();
-    let y = parts[2].parse::<f32>().unwrap();
-    let uses = parts[3].parse::<usize>().unwrap();
+) -> Result<(), ParseErrorReason> {
+    let x = parse_word::<f32>(&parts, 1)?;
+    let y = parse_word::<f32>(&parts, 2)?;
+    let uses = parse_word::<usize>(&parts, 3)?;
     spawn_rock(commands, meshes, materials, Vec3::new(x, y, 0.07), uses);
+    Ok(())
+}
+
+// the chance of each try at placing a rock in a chunk working, depending on the biome it lands in
+fn get_rock_chance(biome: Biome) -> f32 {
+    match biome {
+        Biome::RockyHills => 0.5,
+        Biome::Desert => 0.15,
+        Biome::Forest | Biome::Plains => 0.1,
+        Biome::Lake => 0.,
+    }
 }
 
 pub fn generate_rocks(
     commands: &mut Commands,
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<ColorMaterial>>,
-    asset_server: &Res<AssetServer>,
     pos: (i32, i32),
+    seed: u64,
 ) {
-    let mut rng = rand::thread_rng();
-    for _ in 0..5 {
-        if rng.gen::<f32>() < 0.5 {
-            continue;
-        }
+    let mut rng = get_chunk_rng(seed, pos, ROCK_SALT);
+    for _ in 0..12 {
         let pos = Vec3::new(
             ((rng.gen::<f32>()) + pos.0 as f32) * 16.,
             ((rng.gen::<f32>()) + pos.1 as f32) * 16.,
             0.07,
         );
+        if rng.gen::<f32>() >= get_rock_chance(get_biome(seed, pos.truncate())) {
+            continue;
+        }
         spawn_rock(commands, meshes, materials, pos, 10);
     }
 }
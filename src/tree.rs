@@ -1,4 +1,7 @@
-use crate::{Collectible, ItemType, StaticCollisionCircle};
+use crate::{
+    get_biome, get_chunk_rng, parse_word, Biome, Collectible, ItemType, ParseErrorReason,
+    StaticCollisionCircle, TREE_SALT,
+};
 use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
 use rand::Rng;
 
@@ -26,9 +29,9 @@ pub fn spawn_tree(
             pos: Vec2::new(pos.x, pos.y),
             gather_radius: 3.,
             item_type: ItemType::Wood,
-            uses: uses,
-            normal_material: normal_material,
-            punched_material: punched_material,
+            uses,
+            normal_material,
+            punched_material,
         },
         StaticCollisionCircle { radius: 1. },
     ));
@@ -52,11 +55,22 @@ pub fn spawn_tree_from_string_rep(
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<ColorMaterial>>,
     parts: Vec<&str>, // a vector of strings, containing the words in the string representation
-) {
-    let x = parts[1].parse::<f32>().unwrap();
-    let y = parts[2].parse::<f32>().unwrap();
-    let uses = parts[3].parse::<usize>().unwrap();
+) -> Result<(), ParseErrorReason> {
+    let x = parse_word::<f32>(&parts, 1)?;
+    let y = parse_word::<f32>(&parts, 2)?;
+    let uses = parse_word::<usize>(&parts, 3)?;
     spawn_tree(commands, meshes, materials, Vec3::new(x, y, 0.06), uses);
+    Ok(())
+}
+
+// the chance of each try at placing a tree in a chunk working, depending on the biome it lands in
+fn get_tree_chance(biome: Biome) -> f32 {
+    match biome {
+        Biome::Forest => 0.6,
+        Biome::Plains => 0.12,
+        Biome::RockyHills => 0.08,
+        Biome::Desert | Biome::Lake => 0.,
+    }
 }
 
 pub fn generate_trees(
@@ -64,17 +78,18 @@ pub fn generate_trees(
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<ColorMaterial>>,
     pos: (i32, i32),
+    seed: u64,
 ) {
-    let mut rng = rand::thread_rng();
-    for _ in 0..5 {
-        if rng.gen::<f32>() < 0.5 {
-            continue;
-        }
+    let mut rng = get_chunk_rng(seed, pos, TREE_SALT);
+    for _ in 0..12 {
         let pos = Vec3::new(
             ((rng.gen::<f32>()) + pos.0 as f32) * 16.,
             ((rng.gen::<f32>()) + pos.1 as f32) * 16.,
             0.06,
         );
+        if rng.gen::<f32>() >= get_tree_chance(get_biome(seed, pos.truncate())) {
+            continue;
+        }
         spawn_tree(commands, meshes, materials, pos, 10);
     }
 }
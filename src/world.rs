@@ -0,0 +1,496 @@
+use bevy::prelude::*;
+
+use std::{
+    cmp::Reverse,
+    fs,
+    io::Write,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::parse_word;
+
+// the name of the world that the legacy save gets imported as
+const LEGACY_WORLD_NAME: &str = "world";
+// how often a world is saved while it's being played, in seconds, unless its world.txt says
+// otherwise. it can't be set any lower than MIN_AUTOSAVE_SECONDS, so the game isn't always saving
+const DEFAULT_AUTOSAVE_SECONDS: f32 = 60.;
+const MIN_AUTOSAVE_SECONDS: f32 = 5.;
+
+#[derive(Clone, Debug)]
+pub struct WorldMetadata {
+    pub name: String,
+    pub seed: u64,
+    // when the world was created and last played, in seconds since the unix epoch
+    pub created: u64,
+    pub last_played: u64,
+    // how long the world has been played for altogether, in seconds
+    pub play_time: f64,
+    // if this is on, chunks with block updates still waiting to happen also stay simulated, so
+    // running circuits like clocks don't freeze when the player walks away
+    pub keep_chunks_with_pending_updates: bool,
+    // how often the world is saved while it's being played, in seconds
+    pub autosave_seconds: f32,
+}
+
+impl Default for WorldMetadata {
+    fn default() -> Self {
+        WorldMetadata {
+            name: String::new(),
+            seed: 0,
+            created: 0,
+            last_played: 0,
+            play_time: 0.,
+            keep_chunks_with_pending_updates: true,
+            autosave_seconds: DEFAULT_AUTOSAVE_SECONDS,
+        }
+    }
+}
+
+// the world being played, or the one that was played last
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct CurrentWorld(pub WorldMetadata);
+
+impl CurrentWorld {
+    pub fn get_chunk_file(&self, pos: (i32, i32)) -> String {
+        format!(
+            "{}/chunk_{}_{}.txt",
+            get_world_dir(&self.name),
+            pos.0,
+            pos.1
+        )
+    }
+
+    pub fn get_players_file(&self) -> String {
+        format!("{}/players.txt", get_world_dir(&self.name))
+    }
+
+    pub fn get_chunk_loaders_file(&self) -> String {
+        format!("{}/chunk_loaders.txt", get_world_dir(&self.name))
+    }
+
+    pub fn get_region_file(&self, region: (i32, i32)) -> String {
+        format!(
+            "{}/r_{}_{}.bin",
+            get_regions_dir(&self.name),
+            region.0,
+            region.1
+        )
+    }
+}
+
+// every world is saved in its own directory in here, named after the world
+#[cfg(not(test))]
+pub fn get_saves_dir() -> String {
+    String::from("./saves")
+}
+
+// tests save their worlds somewhere else, so they never touch the real ones. each test runs on a
+// thread named after it, so tests that list worlds don't see each other's
+#[cfg(test)]
+pub fn get_saves_dir() -> String {
+    let test_name = std::thread::current()
+        .name()
+        .unwrap_or("main")
+        .replace("::", "_");
+    std::env::temp_dir()
+        .join(format!(
+            "bevy_game_4_saves_{}_{}",
+            std::process::id(),
+            test_name
+        ))
+        .to_string_lossy()
+        .to_string()
+}
+
+// where the game saved its only world before there were world directories
+#[cfg(not(test))]
+fn get_legacy_save_dir() -> String {
+    String::from("./assets")
+}
+
+#[cfg(test)]
+fn get_legacy_save_dir() -> String {
+    format!("{}_legacy", get_saves_dir())
+}
+
+// left in the saves directory once the legacy save has been looked at, so it's only imported once
+fn get_legacy_imported_marker() -> String {
+    format!("{}/.legacy_imported", get_saves_dir())
+}
+
+pub fn get_world_dir(name: &str) -> String {
+    format!("{}/{}", get_saves_dir(), name)
+}
+
+pub fn get_regions_dir(name: &str) -> String {
+    format!("{}/regions", get_world_dir(name))
+}
+
+// where a world's chunks get exported to as text files
+pub fn get_text_export_dir(name: &str) -> String {
+    format!("{}/text_export", get_world_dir(name))
+}
+
+fn get_world_metadata_file(name: &str) -> String {
+    format!("{}/world.txt", get_world_dir(name))
+}
+
+// writes the whole file to a temporary file next to it first, and only then moves it over the old
+// one. if the game crashes halfway through saving, the old file is still there instead of half of
+// the new one
+pub fn write_file_atomically(file: &str, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    let temp_file = format!("{}.tmp", file);
+    let mut writer = fs::File::create(&temp_file)?;
+    writer.write_all(contents.as_ref())?;
+    writer.sync_all()?;
+    fs::rename(&temp_file, file)
+}
+
+pub fn get_current_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+// world names are used as directory names, so only letters, numbers, spaces, - and _ are allowed,
+// and there can't already be a world with the name
+fn check_world_name(name: &str) -> Result<(), String> {
+    check_world_name_characters(name)?;
+    if fs::metadata(get_world_dir(name)).is_ok() {
+        return Err(format!("there is already a world called {}", name));
+    }
+    Ok(())
+}
+
+fn check_world_name_characters(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err(String::from("the world needs a name"));
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == ' ' || c == '-' || c == '_')
+    {
+        return Err(String::from(
+            "world names can only have letters, numbers, spaces, - and _",
+        ));
+    }
+    Ok(())
+}
+
+pub fn save_world_metadata(metadata: &WorldMetadata) -> Result<(), String> {
+    let metadata_string = format!(
+        "name {}\nseed {}\ncreated {}\nlast_played {}\nplay_time {}\n\
+         keep_chunks_with_pending_updates {}\nautosave_seconds {}\n",
+        metadata.name,
+        metadata.seed,
+        metadata.created,
+        metadata.last_played,
+        metadata.play_time,
+        metadata.keep_chunks_with_pending_updates,
+        metadata.autosave_seconds
+    );
+    write_file_atomically(&get_world_metadata_file(&metadata.name), metadata_string)
+        .map_err(|e| e.to_string())
+}
+
+// the name always comes from the directory, so a world that was moved by hand still works. anything
+// that can't be read is left at its default
+pub fn load_world_metadata(name: &str) -> WorldMetadata {
+    let mut metadata = WorldMetadata {
+        name: String::from(name),
+        ..default()
+    };
+    let Ok(metadata_string) = fs::read_to_string(get_world_metadata_file(name)) else {
+        return metadata;
+    };
+    for line in metadata_string.lines() {
+        let parts = line.split(' ').collect::<Vec<&str>>();
+        match parts[0] {
+            "seed" => metadata.seed = parse_word(&parts, 1).unwrap_or(metadata.seed),
+            "created" => metadata.created = parse_word(&parts, 1).unwrap_or(metadata.created),
+            "last_played" => {
+                metadata.last_played = parse_word(&parts, 1).unwrap_or(metadata.last_played)
+            }
+            "play_time" => metadata.play_time = parse_word(&parts, 1).unwrap_or(metadata.play_time),
+            "keep_chunks_with_pending_updates" => {
+                metadata.keep_chunks_with_pending_updates =
+                    parse_word(&parts, 1).unwrap_or(metadata.keep_chunks_with_pending_updates)
+            }
+            "autosave_seconds" => {
+                metadata.autosave_seconds = parse_word::<f32>(&parts, 1)
+                    .unwrap_or(metadata.autosave_seconds)
+                    .max(MIN_AUTOSAVE_SECONDS)
+            }
+            _ => {}
+        }
+    }
+    metadata
+}
+
+// all the worlds in the saves directory, the most recently played first
+pub fn list_worlds() -> Vec<WorldMetadata> {
+    let mut worlds = Vec::new();
+    if let Ok(entries) = fs::read_dir(get_saves_dir()) {
+        for entry in entries.flatten() {
+            if entry.path().is_dir() {
+                if let Some(name) = entry.file_name().to_str() {
+                    worlds.push(load_world_metadata(name));
+                }
+            }
+        }
+    }
+    worlds.sort_by_key(|world| Reverse(world.last_played));
+    worlds
+}
+
+pub fn create_world(name: &str) -> Result<WorldMetadata, String> {
+    check_world_name(name)?;
+    fs::create_dir_all(get_world_dir(name)).map_err(|e| e.to_string())?;
+    let metadata = WorldMetadata {
+        name: String::from(name),
+        seed: rand::random::<u64>(),
+        created: get_current_time(),
+        last_played: get_current_time(),
+        ..default()
+    };
+    save_world_metadata(&metadata)?;
+    Ok(metadata)
+}
+
+// renaming a world to its own name does nothing. on filesystems that ignore case, a world renamed
+// to its own name in a different case finds itself already there, which doesn't count as another
+// world being in the way
+pub fn rename_world(name: &str, new_name: &str) -> Result<(), String> {
+    if new_name == name {
+        return Ok(());
+    }
+    check_world_name_characters(new_name)?;
+    let dir = fs::canonicalize(get_world_dir(name)).map_err(|e| e.to_string())?;
+    if fs::canonicalize(get_world_dir(new_name)).is_ok_and(|new_dir| new_dir != dir) {
+        return Err(format!("there is already a world called {}", new_name));
+    }
+    fs::rename(get_world_dir(name), get_world_dir(new_name)).map_err(|e| e.to_string())?;
+    let mut metadata = load_world_metadata(new_name);
+    metadata.name = String::from(new_name);
+    save_world_metadata(&metadata)
+}
+
+// the copy keeps the seed and everything that's been built, but counts as a new world
+pub fn copy_world(name: &str, new_name: &str) -> Result<(), String> {
+    check_world_name(new_name)?;
+    copy_dir(&get_world_dir(name), &get_world_dir(new_name)).map_err(|e| e.to_string())?;
+    let mut metadata = load_world_metadata(new_name);
+    metadata.created = get_current_time();
+    metadata.play_time = 0.;
+    save_world_metadata(&metadata)
+}
+
+pub fn delete_world(name: &str) -> Result<(), String> {
+    fs::remove_dir_all(get_world_dir(name)).map_err(|e| e.to_string())
+}
+
+fn copy_dir(from: &str, to: &str) -> std::io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let from_path = format!("{}/{}", from, file_name.to_string_lossy());
+        let to_path = format!("{}/{}", to, file_name.to_string_lossy());
+        if entry.path().is_dir() {
+            copy_dir(&from_path, &to_path)?;
+        } else {
+            fs::copy(&from_path, &to_path)?;
+        }
+    }
+    Ok(())
+}
+
+// before there were world directories, the one world was saved straight into the assets. if there
+// are no worlds yet, that save gets copied into a world of its own so it isn't lost. this only
+// happens once, so deleting the imported world doesn't bring the old save back
+pub fn import_legacy_world() {
+    let legacy_save_dir = get_legacy_save_dir();
+    if fs::metadata(get_legacy_imported_marker()).is_ok()
+        || fs::metadata(format!("{}/players.txt", legacy_save_dir)).is_err()
+    {
+        return;
+    }
+    // if there are already worlds, the old save was imported before there was a marker for it
+    if list_worlds().is_empty() {
+        let metadata = match create_world(LEGACY_WORLD_NAME) {
+            Ok(metadata) => metadata,
+            Err(error) => {
+                println!("error importing the old save: {}", error);
+                return;
+            }
+        };
+        copy_legacy_save(&legacy_save_dir, &metadata.name);
+        println!("imported the old save as the world \"{}\"", metadata.name);
+    }
+    if let Err(error) = fs::write(get_legacy_imported_marker(), "") {
+        println!("error marking the old save as imported: {}", error);
+    }
+}
+
+fn copy_legacy_save(legacy_save_dir: &str, name: &str) {
+    let Ok(entries) = fs::read_dir(legacy_save_dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if file_name == "players.txt"
+            || file_name == "chunk_loaders.txt"
+            || (file_name.starts_with("chunk_") && file_name.ends_with(".txt"))
+        {
+            let to = format!("{}/{}", get_world_dir(name), file_name);
+            if fs::copy(entry.path(), to).is_err() {
+                println!("error importing {} from the old save", file_name);
+            }
+        }
+    }
+}
+
+// adds up how long the current world has been played for
+pub fn update_play_time(time: Res<Time>, mut current_world: ResMut<CurrentWorld>) {
+    current_world.play_time += time.delta_seconds_f64();
+}
+
+pub fn save_current_world_metadata(mut current_world: ResMut<CurrentWorld>) {
+    current_world.last_played = get_current_time();
+    if let Err(error) = save_world_metadata(&current_world) {
+        println!("error saving world {}: {}", current_world.name, error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // makes a world in the test saves directory with a file in it and in its regions directory
+    fn make_test_world(name: &str) -> WorldMetadata {
+        let metadata = create_world(name).unwrap();
+        fs::write(
+            format!("{}/players.txt", get_world_dir(name)),
+            "slot wood 5\n",
+        )
+        .unwrap();
+        fs::create_dir_all(get_regions_dir(name)).unwrap();
+        fs::write(format!("{}/r_0_0.bin", get_regions_dir(name)), [1, 2, 3]).unwrap();
+        metadata
+    }
+
+    #[test]
+    fn world_names_are_checked() {
+        make_test_world("check name");
+        assert!(check_world_name("").is_err());
+        assert!(check_world_name("   ").is_err());
+        assert!(check_world_name("../outside").is_err());
+        assert!(check_world_name("check name").is_err());
+        assert_eq!(check_world_name("check name 2"), Ok(()));
+        delete_world("check name").unwrap();
+        assert_eq!(check_world_name("check name"), Ok(()));
+    }
+
+    // the copy has everything the world had, including its seed, but starts its play time again
+    #[test]
+    fn copied_world_keeps_its_files_and_seed() {
+        let mut metadata = make_test_world("copy_from");
+        metadata.play_time = 100.;
+        save_world_metadata(&metadata).unwrap();
+
+        copy_world("copy_from", "copy_to").unwrap();
+        let copy = load_world_metadata("copy_to");
+        assert_eq!(copy.seed, metadata.seed);
+        assert_eq!(copy.play_time, 0.);
+        assert_eq!(
+            fs::read_to_string(format!("{}/players.txt", get_world_dir("copy_to"))).unwrap(),
+            "slot wood 5\n"
+        );
+        assert_eq!(
+            fs::read(format!("{}/r_0_0.bin", get_regions_dir("copy_to"))).unwrap(),
+            [1, 2, 3]
+        );
+        // the world it was copied from is still there
+        assert_eq!(load_world_metadata("copy_from").play_time, 100.);
+        assert!(copy_world("copy_from", "copy_to").is_err());
+
+        delete_world("copy_from").unwrap();
+        delete_world("copy_to").unwrap();
+    }
+
+    #[test]
+    fn renamed_world_moves_to_its_new_name() {
+        let metadata = make_test_world("rename_from");
+        make_test_world("rename_other");
+
+        assert_eq!(rename_world("rename_from", "rename_from"), Ok(()));
+        assert!(rename_world("rename_from", "rename_other").is_err());
+        assert!(rename_world("rename_from", "bad/name").is_err());
+
+        rename_world("rename_from", "rename_to").unwrap();
+        assert!(fs::metadata(get_world_dir("rename_from")).is_err());
+        let renamed = load_world_metadata("rename_to");
+        assert_eq!(renamed.name, "rename_to");
+        assert_eq!(renamed.seed, metadata.seed);
+        assert!(fs::metadata(format!("{}/r_0_0.bin", get_regions_dir("rename_to"))).is_ok());
+
+        // only the case changes, which is the same directory on some filesystems
+        rename_world("rename_to", "Rename_To").unwrap();
+        assert_eq!(load_world_metadata("Rename_To").seed, metadata.seed);
+
+        delete_world("Rename_To").unwrap();
+        delete_world("rename_other").unwrap();
+    }
+
+    // the old save is imported into a world the first time, but not again after that world is
+    // deleted
+    #[test]
+    fn legacy_save_is_only_imported_once() {
+        fs::create_dir_all(get_legacy_save_dir()).unwrap();
+        fs::write(
+            format!("{}/players.txt", get_legacy_save_dir()),
+            "slot wood 5\n",
+        )
+        .unwrap();
+        fs::write(
+            format!("{}/chunk_0_0.txt", get_legacy_save_dir()),
+            "block wire 1 2 0\n",
+        )
+        .unwrap();
+
+        import_legacy_world();
+        assert_eq!(
+            fs::read_to_string(format!("{}/players.txt", get_world_dir(LEGACY_WORLD_NAME)))
+                .unwrap(),
+            "slot wood 5\n"
+        );
+        assert!(fs::metadata(format!(
+            "{}/chunk_0_0.txt",
+            get_world_dir(LEGACY_WORLD_NAME)
+        ))
+        .is_ok());
+
+        delete_world(LEGACY_WORLD_NAME).unwrap();
+        import_legacy_world();
+        assert!(list_worlds().is_empty());
+
+        fs::remove_dir_all(get_legacy_save_dir()).unwrap();
+        fs::remove_dir_all(get_saves_dir()).unwrap();
+    }
+
+    #[test]
+    fn files_are_written_atomically() {
+        fs::create_dir_all(get_saves_dir()).unwrap();
+        let file = format!("{}/atomic.txt", get_saves_dir());
+        write_file_atomically(&file, "first\n").unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "first\n");
+
+        // an existing file is replaced, not added to
+        write_file_atomically(&file, "second\n").unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "second\n");
+        assert!(fs::metadata(format!("{}.tmp", file)).is_err());
+
+        fs::remove_dir_all(get_saves_dir()).unwrap();
+    }
+}
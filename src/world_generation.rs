@@ -0,0 +1,245 @@
+use bevy::prelude::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+// every kind of thing that's generated in a chunk gets its own random numbers, so adding a new
+// kind doesn't move the ones that were already there
+pub const TREE_SALT: u64 = 1;
+pub const ROCK_SALT: u64 = 2;
+pub const CACTUS_SALT: u64 = 3;
+pub const BOULDER_SALT: u64 = 4;
+pub const ORE_SALT: u64 = 5;
+// the noise that biomes are picked from, and the small differences in colour between tiles
+const HEIGHT_SALT: u64 = 100;
+const MOISTURE_SALT: u64 = 101;
+const TILE_SHADE_SALT: u64 = 102;
+pub const DIRT_PATCH_SALT: u64 = 103;
+
+// roughly how many tiles wide a biome is
+const BIOME_SCALE: f32 = 96.;
+// the number of layers of noise added together. each one is half the size and half as strong as
+// the one before it, which makes the edges of biomes wiggly instead of blobby
+const NOISE_OCTAVES: u32 = 3;
+// ground lower than this is under a lake, and ground higher than HILLS_HEIGHT is rocky hills
+const LAKE_HEIGHT: f32 = 0.33;
+const HILLS_HEIGHT: f32 = 0.67;
+// the ground around lakes that's lower than this is a sandy beach
+pub const BEACH_HEIGHT: f32 = 0.36;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Biome {
+    Forest,
+    Plains,
+    RockyHills,
+    Desert,
+    Lake,
+}
+
+// mixes the seed, a position and a salt into one number that looks random
+fn hash_position(seed: u64, pos: (i32, i32), salt: u64) -> u64 {
+    let mut hash = seed;
+    for value in [pos.0 as u32 as u64, pos.1 as u32 as u64, salt] {
+        hash = (hash ^ value).wrapping_mul(0x100000001b3);
+        hash ^= hash >> 32;
+    }
+    hash
+}
+
+// the random numbers used to generate a chunk. they only depend on the world's seed, the chunk and
+// what's being generated, so a chunk always generates the same way in the same world.
+// chacha is used instead of StdRng because StdRng is allowed to change between versions of rand
+pub fn get_chunk_rng(seed: u64, pos: (i32, i32), salt: u64) -> ChaCha8Rng {
+    ChaCha8Rng::seed_from_u64(hash_position(seed, pos, salt))
+}
+
+// a random number between 0 and 1 for every whole position. the hash gets scrambled some more
+// first (splitmix64's finalizer), since neighbouring positions have similar hashes
+fn get_lattice_value(seed: u64, pos: (i32, i32), salt: u64) -> f32 {
+    let mut hash = hash_position(seed, pos, salt);
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d049bb133111eb);
+    hash ^= hash >> 31;
+    (hash >> 40) as f32 / (1u64 << 24) as f32
+}
+
+// how much lighter or darker than normal a tile is drawn, so the ground doesn't look flat
+pub fn get_tile_shade(seed: u64, tile: (i32, i32)) -> f32 {
+    0.93 + get_lattice_value(seed, tile, TILE_SHADE_SALT) * 0.14
+}
+
+// value noise: random values at whole positions, smoothly blended in between. nearby positions get
+// similar values, so it makes blobs instead of static
+fn get_value_noise(seed: u64, salt: u64, pos: Vec2) -> f32 {
+    let corner = pos.floor();
+    let t = pos - corner;
+    let t = t * t * (Vec2::splat(3.) - 2. * t);
+    let (x, y) = (corner.x as i32, corner.y as i32);
+    let bottom = get_lattice_value(seed, (x, y), salt)
+        + (get_lattice_value(seed, (x + 1, y), salt) - get_lattice_value(seed, (x, y), salt)) * t.x;
+    let top = get_lattice_value(seed, (x, y + 1), salt)
+        + (get_lattice_value(seed, (x + 1, y + 1), salt)
+            - get_lattice_value(seed, (x, y + 1), salt))
+            * t.x;
+    bottom + (top - bottom) * t.y
+}
+
+// several octaves of value noise added together, between 0 and 1
+pub fn get_fractal_noise(seed: u64, salt: u64, pos: Vec2, scale: f32) -> f32 {
+    let mut total = 0.;
+    let mut total_strength = 0.;
+    let mut strength = 1.;
+    let mut pos = pos / scale;
+    for octave in 0..NOISE_OCTAVES {
+        total += get_value_noise(seed, salt + octave as u64 * 1000, pos) * strength;
+        total_strength += strength;
+        strength /= 2.;
+        pos *= 2.;
+    }
+    total / total_strength
+}
+
+pub fn get_height(seed: u64, pos: Vec2) -> f32 {
+    get_fractal_noise(seed, HEIGHT_SALT, pos, BIOME_SCALE)
+}
+
+// low ground is lakes and high ground is rocky hills. in between, it depends on how wet it is
+pub fn get_biome(seed: u64, pos: Vec2) -> Biome {
+    let height = get_height(seed, pos);
+    let moisture = get_fractal_noise(seed, MOISTURE_SALT, pos, BIOME_SCALE * 1.5);
+    if height < LAKE_HEIGHT {
+        Biome::Lake
+    } else if height > HILLS_HEIGHT {
+        Biome::RockyHills
+    } else if moisture < 0.38 {
+        Biome::Desert
+    } else if moisture > 0.58 {
+        Biome::Forest
+    } else {
+        Biome::Plains
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate_boulders, generate_cacti, Boulder, Cactus, Collectible};
+    use bevy::ecs::system::RunSystemOnce;
+    use rand::Rng;
+
+    fn get_first_values(seed: u64, pos: (i32, i32), salt: u64) -> [u64; 4] {
+        let mut rng = get_chunk_rng(seed, pos, salt);
+        [rng.gen(), rng.gen(), rng.gen(), rng.gen()]
+    }
+
+    // worlds are generated from these numbers, so if they ever change, every existing world would
+    // generate differently where it hasn't been explored yet
+    #[test]
+    fn chunk_rng_gives_the_same_numbers_every_time() {
+        assert_eq!(
+            get_first_values(12345, (3, -7), TREE_SALT),
+            [
+                10018302453272111993,
+                10509907201996421822,
+                7262740243961495707,
+                1784832187871816577
+            ]
+        );
+    }
+
+    #[test]
+    fn chunk_rng_is_different_for_other_salts_and_chunks() {
+        let values = get_first_values(12345, (3, -7), TREE_SALT);
+        assert_ne!(get_first_values(12345, (3, -7), ROCK_SALT), values);
+        assert_ne!(get_first_values(12345, (-7, 3), TREE_SALT), values);
+        assert_ne!(get_first_values(12345, (4, -7), TREE_SALT), values);
+        assert_ne!(get_first_values(54321, (3, -7), TREE_SALT), values);
+    }
+
+    // one position every 32 tiles over a big square of the world, so every biome shows up in it
+    fn sample_positions() -> Vec<Vec2> {
+        (-50..50)
+            .flat_map(|x| (-50..50).map(move |y| Vec2::new(x as f32 * 32., y as f32 * 32.)))
+            .collect()
+    }
+
+    #[test]
+    fn biomes_are_the_same_every_time() {
+        for pos in sample_positions() {
+            assert_eq!(get_biome(12345, pos), get_biome(12345, pos));
+        }
+        let biomes = |seed| {
+            sample_positions()
+                .into_iter()
+                .map(|pos| get_biome(seed, pos))
+                .collect::<Vec<_>>()
+        };
+        assert_ne!(biomes(12345), biomes(54321));
+    }
+
+    #[test]
+    fn every_biome_is_generated_somewhere() {
+        let biomes = sample_positions()
+            .into_iter()
+            .map(|pos| get_biome(12345, pos))
+            .collect::<Vec<_>>();
+        for biome in [
+            Biome::Forest,
+            Biome::Plains,
+            Biome::RockyHills,
+            Biome::Desert,
+            Biome::Lake,
+        ] {
+            assert!(biomes.contains(&biome), "{:?} never generated", biome);
+        }
+    }
+
+    // generate_cacti, generate_boulders and the rest all take the same arguments
+    type Generate = fn(
+        &mut Commands,
+        &mut ResMut<Assets<Mesh>>,
+        &mut ResMut<Assets<ColorMaterial>>,
+        (i32, i32),
+        u64,
+    );
+
+    // where generate_cacti and generate_boulders put things in a square of chunks
+    fn generate_in_chunks<T: Component>(generate: Generate) -> Vec<Vec2> {
+        let mut world = World::new();
+        world.insert_resource(Assets::<Mesh>::default());
+        world.insert_resource(Assets::<ColorMaterial>::default());
+        for x in -10..10 {
+            for y in -10..10 {
+                world.run_system_once(
+                    move |mut commands: Commands,
+                          mut meshes: ResMut<Assets<Mesh>>,
+                          mut materials: ResMut<Assets<ColorMaterial>>| {
+                        generate(&mut commands, &mut meshes, &mut materials, (x, y), 12345);
+                    },
+                );
+            }
+        }
+        let mut generated = world.query_filtered::<&Collectible, With<T>>();
+        generated
+            .iter(&world)
+            .map(|collectible| collectible.pos)
+            .collect()
+    }
+
+    #[test]
+    fn cacti_only_grow_in_deserts() {
+        let cacti = generate_in_chunks::<Cactus>(generate_cacti);
+        assert!(!cacti.is_empty());
+        for pos in cacti {
+            assert_eq!(get_biome(12345, pos), Biome::Desert);
+        }
+    }
+
+    #[test]
+    fn boulders_are_only_in_rocky_hills() {
+        let boulders = generate_in_chunks::<Boulder>(generate_boulders);
+        assert!(!boulders.is_empty());
+        for pos in boulders {
+            assert_eq!(get_biome(12345, pos), Biome::RockyHills);
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use bevy::prelude::*;
+
+use crate::{
+    copy_world, create_world, delete_world, export_world_as_text, get_current_time,
+    get_text_export_dir, import_legacy_world, list_worlds, rename_world, AppState, CurrentWorld,
+    WorldMetadata,
+};
+
+// what the name being typed in the menu is for
+#[derive(Clone, Copy, PartialEq)]
+pub enum WorldMenuPrompt {
+    Create,
+    Rename,
+    Copy,
+}
+
+#[derive(Resource, Default)]
+pub struct WorldMenu {
+    pub worlds: Vec<WorldMetadata>,
+    pub selected: usize,
+    pub prompt: Option<WorldMenuPrompt>,
+    pub input: String,
+    // shown under the list, for errors and to ask before deleting a world
+    pub message: String,
+    pub confirm_delete: bool,
+}
+
+#[derive(Component)]
+pub struct WorldMenuUI;
+
+impl Plugin for WorldMenuUI {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(WorldMenu::default());
+        app.add_systems(OnEnter(AppState::MainMenu), spawn_world_menu_ui);
+        app.add_systems(OnExit(AppState::MainMenu), despawn_world_menu_ui);
+        app.add_systems(
+            Update,
+            (
+                world_menu_input_system,
+                update_world_menu_ui.after(world_menu_input_system),
+            )
+                .run_if(in_state(AppState::MainMenu)),
+        );
+    }
+}
+
+pub fn spawn_world_menu_ui(mut commands: Commands, mut world_menu: ResMut<WorldMenu>) {
+    import_legacy_world();
+    refresh_worlds(&mut world_menu);
+    world_menu.message.clear();
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 24.,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(40.),
+            left: Val::Px(40.),
+            ..default()
+        }),
+        WorldMenuUI {},
+    ));
+}
+
+pub fn despawn_world_menu_ui(
+    world_menu_ui: Query<Entity, With<WorldMenuUI>>,
+    mut commands: Commands,
+) {
+    for entity in world_menu_ui.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn refresh_worlds(world_menu: &mut WorldMenu) {
+    world_menu.worlds = list_worlds();
+    world_menu.selected = world_menu
+        .selected
+        .min(world_menu.worlds.len().saturating_sub(1));
+}
+
+// arrow keys pick a world, g or enter plays it, n makes a new one, r renames it, c copies it, e
+// exports it as text files and delete deletes it after asking. while a name is being typed, enter
+// confirms and escape cancels
+fn world_menu_input_system(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut characters: EventReader<ReceivedCharacter>,
+    mut world_menu: ResMut<WorldMenu>,
+    mut current_world: ResMut<CurrentWorld>,
+) {
+    if let Some(prompt) = world_menu.prompt {
+        for character in characters.read() {
+            if character.char.is_ascii_alphanumeric()
+                || character.char == ' '
+                || character.char == '-'
+                || character.char == '_'
+            {
+                world_menu.input.push(character.char);
+            }
+        }
+        if keyboard_input.just_pressed(KeyCode::Back) {
+            world_menu.input.pop();
+        }
+        if keyboard_input.just_pressed(KeyCode::Escape) {
+            world_menu.prompt = None;
+        }
+        if keyboard_input.just_pressed(KeyCode::Return) {
+            let input = world_menu.input.trim().to_string();
+            let selected_name = world_menu
+                .worlds
+                .get(world_menu.selected)
+                .map(|world| world.name.clone())
+                .unwrap_or_default();
+            let result = match prompt {
+                WorldMenuPrompt::Create => create_world(&input).map(|_| ()),
+                WorldMenuPrompt::Rename => rename_world(&selected_name, &input),
+                WorldMenuPrompt::Copy => copy_world(&selected_name, &input),
+            };
+            match result {
+                Ok(_) => {
+                    world_menu.prompt = None;
+                    world_menu.message.clear();
+                    refresh_worlds(&mut world_menu);
+                    if let Some(i) = world_menu.worlds.iter().position(|w| w.name == input) {
+                        world_menu.selected = i;
+                    }
+                }
+                Err(error) => world_menu.message = error,
+            }
+        }
+        return;
+    }
+    characters.clear();
+
+    if keyboard_input.just_pressed(KeyCode::N) {
+        world_menu.prompt = Some(WorldMenuPrompt::Create);
+        world_menu.input.clear();
+        world_menu.message.clear();
+        world_menu.confirm_delete = false;
+        return;
+    }
+    if world_menu.worlds.is_empty() {
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Up) && world_menu.selected > 0 {
+        world_menu.selected -= 1;
+        world_menu.confirm_delete = false;
+    }
+    if keyboard_input.just_pressed(KeyCode::Down)
+        && world_menu.selected + 1 < world_menu.worlds.len()
+    {
+        world_menu.selected += 1;
+        world_menu.confirm_delete = false;
+    }
+    let selected_name = world_menu.worlds[world_menu.selected].name.clone();
+    if keyboard_input.just_pressed(KeyCode::R) || keyboard_input.just_pressed(KeyCode::C) {
+        world_menu.prompt = if keyboard_input.just_pressed(KeyCode::R) {
+            Some(WorldMenuPrompt::Rename)
+        } else {
+            Some(WorldMenuPrompt::Copy)
+        };
+        world_menu.input = selected_name;
+        world_menu.message.clear();
+        world_menu.confirm_delete = false;
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::E) {
+        world_menu.confirm_delete = false;
+        world_menu.message = match export_world_as_text(&selected_name) {
+            Ok(exported) => format!(
+                "exported {} chunks to {}",
+                exported,
+                get_text_export_dir(&selected_name)
+            ),
+            Err(error) => error,
+        };
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::Delete) {
+        if world_menu.confirm_delete {
+            world_menu.confirm_delete = false;
+            world_menu.message = match delete_world(&selected_name) {
+                Ok(_) => format!("deleted {}", selected_name),
+                Err(error) => error,
+            };
+            refresh_worlds(&mut world_menu);
+        } else {
+            world_menu.confirm_delete = true;
+            world_menu.message = format!("press delete again to delete {} forever", selected_name);
+        }
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::G) || keyboard_input.just_pressed(KeyCode::Return) {
+        current_world.0 = world_menu.worlds[world_menu.selected].clone();
+        commands.insert_resource(NextState(Some(AppState::Game)));
+        println!("Entered AppState::Game in world {}", current_world.name);
+    }
+}
+
+fn update_world_menu_ui(
+    world_menu: Res<WorldMenu>,
+    mut world_menu_ui: Query<&mut Text, With<WorldMenuUI>>,
+) {
+    if !world_menu.is_changed() {
+        return;
+    }
+    let mut text = String::from("Worlds\n\n");
+    if world_menu.worlds.is_empty() {
+        text.push_str("There are no worlds yet.\n");
+    }
+    let now = get_current_time();
+    for (i, world) in world_menu.worlds.iter().enumerate() {
+        text.push_str(
+            format!(
+                "{} {}    seed {}, played for {}, last played {} ago\n",
+                if i == world_menu.selected { ">" } else { " " },
+                world.name,
+                world.seed,
+                format_duration(world.play_time as u64),
+                format_duration(now.saturating_sub(world.last_played))
+            )
+            .as_str(),
+        );
+    }
+    text.push('\n');
+    if let Some(prompt) = world_menu.prompt {
+        let question = match prompt {
+            WorldMenuPrompt::Create => "Name of the new world",
+            WorldMenuPrompt::Rename => "New name",
+            WorldMenuPrompt::Copy => "Name of the copy",
+        };
+        text.push_str(format!("{}: {}_\n", question, world_menu.input).as_str());
+        text.push_str("enter: done   esc: cancel\n");
+    } else {
+        text.push_str(
+            "up/down: pick   g/enter: play   n: new   r: rename   c: copy   e: export as text   delete: delete\n",
+        );
+    }
+    text.push_str(world_menu.message.as_str());
+
+    for mut world_menu_text in &mut world_menu_ui {
+        world_menu_text.sections[0].value = text.clone();
+    }
+}
+
+// like 3d 4h, 5m 10s or 42s, only showing the two biggest units
+fn format_duration(seconds: u64) -> String {
+    let units = [(86400, "d"), (3600, "h"), (60, "m"), (1, "s")];
+    let mut parts = Vec::new();
+    let mut rest = seconds;
+    for (size, unit) in units {
+        if rest >= size || (parts.is_empty() && size == 1) {
+            parts.push(format!("{}{}", rest / size, unit));
+            rest %= size;
+        }
+        if parts.len() == 2 {
+            break;
+        }
+    }
+    parts.join(" ")
+}